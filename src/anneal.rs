@@ -0,0 +1,85 @@
+//! Annealing-style schedule of the minimal distance. A single pass of WSP on a strongly clustered
+//! candidate set lingers in the dense clusters, since the next origin is always the closest valid point.
+//! Starting with a large minimal distance spreads a coarse skeleton over the whole space, which the
+//! following passes refine with decreasing minimal distances.
+
+use crate::{random_origin, resolve_timed, wsp_loop_fast, DMin, PointSet, RunStats};
+
+/// Executes the WSP algorithm in `nb_passes` passes of decreasing minimal distances.
+/// Pass `k` (starting from 0) uses the minimal distance `d_min * cooling^(k - nb_passes + 1)`, such that
+/// the last pass uses `d_min`. Each pass starts again from the points active before the first pass,
+/// and keeps all the points selected by the previous pass: the final design contains the coarse designs.
+/// Returns the statistics accumulated over all the passes.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The minimal distance of the last pass. See [`crate::wsp`].
+/// * `nb_passes` - The number of passes. With a single pass, this is the classical algorithm.
+/// * `cooling` - The ratio between the minimal distances of two consecutive passes, in (0, 1).
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+/// wsp::wsp_annealed(&mut points, 0.05, 3, 0.5);
+/// ```
+pub fn wsp_annealed(
+    set: &mut PointSet,
+    d_min: impl Into<DMin>,
+    nb_passes: usize,
+    cooling: f64,
+) -> RunStats {
+    assert!(nb_passes > 0, "The number of passes must be positive");
+    assert!(
+        cooling > 0.0 && cooling < 1.0,
+        "The cooling ratio must be in (0, 1), got {}",
+        cooling
+    );
+    let (d_min, mut stats) = resolve_timed(set, d_min.into());
+    let initial_active = set.active.clone();
+    let initial_fixed = set.fixed.clone();
+
+    for pass in 0..nb_passes {
+        let pass_d_min = d_min / cooling.powi((nb_passes - 1 - pass) as i32);
+        if pass > 0 {
+            // Keep the points of the previous pass, and give back the other points a chance
+            for i in 0..set.points.len() {
+                set.fixed[i] |= set.active[i];
+            }
+            set.active.clone_from(&initial_active);
+            set.nb_active = set.active.count_ones();
+            set.reset_visited();
+        }
+        let origin = random_origin(set);
+        wsp_loop_fast(set, pass_d_min, origin, &mut stats);
+    }
+    set.fixed = initial_fixed;
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsp;
+
+    #[test]
+    fn test_annealed_contains_coarse_design() {
+        let points = PointSet::init_from_random(1000, 2, 51);
+        let mut single = PointSet::init_from_preset(points.points.clone());
+        wsp_annealed(&mut single, 0.1, 1, 0.5);
+        let mut classic = PointSet::init_from_preset(points.points.clone());
+        wsp(&mut classic, 0.1);
+        assert_eq!(single.active, classic.active);
+
+        let mut coarse = PointSet::init_from_preset(points.points.clone());
+        wsp(&mut coarse, 0.2);
+        let mut annealed = PointSet::init_from_preset(points.points);
+        wsp_annealed(&mut annealed, 0.1, 2, 0.5);
+
+        assert!((0..1000).all(|i| !coarse.active[i] || annealed.active[i]));
+        assert_eq!(annealed.nb_active, annealed.active.count_ones());
+        assert!(annealed.nb_active > coarse.nb_active);
+        assert!(annealed.fixed.iter().all(|&f| !f));
+    }
+}
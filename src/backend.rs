@@ -0,0 +1,83 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of points up to which the dense backend is always chosen.
+const DENSE_MAX_POINTS: usize = 4_000;
+/// In high dimensions, space partitioning trees degrade, so the dense backend is kept for more points.
+const DENSE_MAX_POINTS_HIGH_DIM: usize = 10_000;
+/// Dimension above which a space partitioning tree is considered inefficient.
+const HIGH_DIM: usize = 20;
+/// Dimension up to which the grid backend is chosen for large sets: each origin scans 3^dim cells.
+const GRID_MAX_DIM: usize = 6;
+/// Number of points up to which the exact tree backend is chosen.
+const KDTREE_MAX_POINTS: usize = 1_000_000;
+
+/// Algorithm used to find the neighbours of the origins during the WSP algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Stores the full distance matrix and, for each point, all other points sorted by distance.
+    /// Fastest algorithm, but requires O(n²) memory.
+    Dense,
+    /// Matrix-free algorithm using a KD-tree to find the neighbours of the origins. Requires O(n) memory.
+    KdTree,
+    /// Same as `KdTree`, but the next origin is only an approximation of the closest valid point.
+    /// The minimal distance between the remaining points is still guaranteed.
+    Approximate,
+    /// Matrix-free algorithm bucketing the points in a uniform grid whose cells have the size of the
+    /// minimal distance, so that the neighbours of an origin lie in the adjacent cells. Requires O(n)
+    /// memory, and is faster than the KD-tree in low dimensions only.
+    Grid,
+}
+
+impl Backend {
+    /// Heuristic choosing a reasonable backend given the size of the problem:
+    /// the dense backend for small sets, the grid for large sets in low dimensions,
+    /// the KD-tree for large sets, and the approximate backend for huge sets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// assert_eq!(wsp::Backend::auto(1000, 20), wsp::Backend::Dense);
+    /// assert_eq!(wsp::Backend::auto(100_000, 3), wsp::Backend::Grid);
+    /// assert_eq!(wsp::Backend::auto(100_000, 10), wsp::Backend::KdTree);
+    /// ```
+    pub fn auto(nb_points: usize, nb_dim: usize) -> Backend {
+        if nb_points <= DENSE_MAX_POINTS
+            || (nb_dim > HIGH_DIM && nb_points <= DENSE_MAX_POINTS_HIGH_DIM)
+        {
+            Backend::Dense
+        } else if nb_dim <= GRID_MAX_DIM {
+            Backend::Grid
+        } else if nb_points <= KDTREE_MAX_POINTS {
+            Backend::KdTree
+        } else {
+            Backend::Approximate
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Backend::Dense => "dense",
+            Backend::KdTree => "kdtree",
+            Backend::Approximate => "approximate",
+            Backend::Grid => "grid",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Backend, String> {
+        match s {
+            "dense" => Ok(Backend::Dense),
+            "kdtree" => Ok(Backend::KdTree),
+            "approximate" => Ok(Backend::Approximate),
+            "grid" => Ok(Backend::Grid),
+            _ => Err(format!("Unknown backend: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Little-endian helpers for the binary file formats of the crate.
+
+use std::io::{self, Read, Write};
+
+pub(crate) fn write_u64<W: Write>(wrt: &mut W, value: u64) -> io::Result<()> {
+    wrt.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn write_f64<W: Write>(wrt: &mut W, value: f64) -> io::Result<()> {
+    wrt.write_all(&value.to_le_bytes())
+}
+
+pub(crate) fn read_u64<R: Read>(rdr: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    rdr.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_f64<R: Read>(rdr: &mut R) -> io::Result<f64> {
+    let mut buf = [0u8; 8];
+    rdr.read_exact(&mut buf)?;
+    Ok(f64::from_le_bytes(buf))
+}
+
+pub(crate) fn read_usize<R: Read>(rdr: &mut R) -> io::Result<usize> {
+    read_u64(rdr)?
+        .try_into()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "value does not fit in usize"))
+}
+
+/// Checks that the reader starts with the expected magic bytes.
+pub(crate) fn expect_magic<R: Read>(rdr: &mut R, magic: &[u8]) -> io::Result<()> {
+    let mut buf = vec![0u8; magic.len()];
+    rdr.read_exact(&mut buf)?;
+    if buf != magic {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected file format",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Compact storage of boolean flags, one bit per point. Runs of unset bits are skipped
+//! a whole word at a time when searching for the next set bit.
+
+use std::ops::Index;
+
+const WORD_BITS: usize = 64;
+
+/// Fixed-size set of bits, packed in 64-bit words. Reading a bit works like a `Vec<bool>`:
+///
+/// ```
+/// let mut bits: wsp::BitSet = vec![true, false, true].into_iter().collect();
+/// assert!(bits[0] && !bits[1]);
+/// bits.set(1, true);
+/// assert_eq!(bits.count_ones(), 3);
+/// assert_eq!(bits.to_vec(), vec![true; 3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// Creates a set of `len` bits, all equal to `value`.
+    pub fn new(len: usize, value: bool) -> BitSet {
+        let fill = if value { u64::MAX } else { 0 };
+        let mut bits = BitSet {
+            words: vec![fill; len.div_ceil(WORD_BITS)],
+            len,
+        };
+        bits.clear_padding();
+        bits
+    }
+
+    /// Unsets the bits of the last word beyond the length, so that the words can be counted and scanned.
+    fn clear_padding(&mut self) {
+        if !self.len.is_multiple_of(WORD_BITS) {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << (self.len % WORD_BITS)) - 1;
+            }
+        }
+    }
+
+    /// Returns the number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the set contains no bits.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit at index `i`.
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "index {} out of {} bits", i, self.len);
+        self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    /// Sets the bit at index `i` to `value`.
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.len, "index {} out of {} bits", i, self.len);
+        let mask = 1 << (i % WORD_BITS);
+        if value {
+            self.words[i / WORD_BITS] |= mask;
+        } else {
+            self.words[i / WORD_BITS] &= !mask;
+        }
+    }
+
+    /// Sets all the bits to `value`.
+    pub fn fill(&mut self, value: bool) {
+        let fill = if value { u64::MAX } else { 0 };
+        self.words.iter_mut().for_each(|w| *w = fill);
+        self.clear_padding();
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Iterates over the bits, in order.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + Clone + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// Iterates over the indexes of the set bits, in increasing order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + Clone + '_ {
+        let mut next = self.next_one(0);
+        std::iter::from_fn(move || {
+            let current = next?;
+            next = self.next_one(current + 1);
+            Some(current)
+        })
+    }
+
+    /// Returns the index of the first set bit at or after `from`.
+    pub fn next_one(&self, from: usize) -> Option<usize> {
+        self.next_one_masked(from, |w| self.words[w])
+    }
+
+    /// Returns the index of the first bit at or after `from` that is set in `self` but not in `other`.
+    pub(crate) fn next_one_and_not(&self, other: &BitSet, from: usize) -> Option<usize> {
+        self.next_one_masked(from, |w| self.words[w] & !other.words[w])
+    }
+
+    /// Scans the words given by `word` from the bit `from`, skipping the empty words at once.
+    fn next_one_masked<F: Fn(usize) -> u64>(&self, from: usize, word: F) -> Option<usize> {
+        if from >= self.len {
+            return None;
+        }
+        let mut w = from / WORD_BITS;
+        let mut bits = word(w) & (u64::MAX << (from % WORD_BITS));
+        loop {
+            if bits != 0 {
+                return Some(w * WORD_BITS + bits.trailing_zeros() as usize);
+            }
+            w += 1;
+            if w == self.words.len() {
+                return None;
+            }
+            bits = word(w);
+        }
+    }
+
+    /// Returns the bits as booleans.
+    pub fn to_vec(&self) -> Vec<bool> {
+        self.iter().collect()
+    }
+}
+
+impl Index<usize> for BitSet {
+    type Output = bool;
+
+    fn index(&self, i: usize) -> &bool {
+        if self.get(i) {
+            &true
+        } else {
+            &false
+        }
+    }
+}
+
+impl FromIterator<bool> for BitSet {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> BitSet {
+        let mut bits = BitSet::default();
+        for (i, value) in iter.into_iter().enumerate() {
+            if i.is_multiple_of(WORD_BITS) {
+                bits.words.push(0);
+            }
+            bits.words[i / WORD_BITS] |= (value as u64) << (i % WORD_BITS);
+            bits.len += 1;
+        }
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_scanning() {
+        let flags: Vec<bool> = (0..150).map(|i| i % 3 == 0 || i == 149).collect();
+        let bits: BitSet = flags.iter().copied().collect();
+        assert_eq!(bits.len(), 150);
+        assert_eq!(bits.count_ones(), flags.iter().filter(|&&f| f).count());
+        assert_eq!(bits.to_vec(), flags);
+        let ones: Vec<usize> = bits.iter_ones().collect();
+        assert_eq!(ones, (0..150).filter(|&i| flags[i]).collect::<Vec<usize>>());
+
+        let mut sparse = BitSet::new(300, false);
+        assert_eq!(sparse.next_one(0), None);
+        sparse.set(299, true);
+        sparse.set(70, true);
+        assert_eq!(sparse.next_one(0), Some(70));
+        assert_eq!(sparse.next_one(71), Some(299));
+        let mut mask = BitSet::new(300, false);
+        mask.set(70, true);
+        assert_eq!(sparse.next_one_and_not(&mask, 0), Some(299));
+
+        let mut full = BitSet::new(130, true);
+        assert_eq!(full.count_ones(), 130);
+        full.fill(false);
+        assert_eq!(full.next_one(0), None);
+    }
+}
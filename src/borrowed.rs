@@ -0,0 +1,247 @@
+//! Zero-copy variant of the PointSet, for huge candidate arrays owned by the caller (e.g. memory-mapped
+//! files or the buffer of an `ndarray` view). The coordinates are read in place from a row-major slice:
+//! only the KD-tree over the point indexes and the flags of the points are allocated.
+
+use crate::kdtree::{Coordinates, KdTree, TreeWalk};
+use crate::{
+    draw_origin, BitSet, Metric, RemovalObserver, RngAlgorithm, RunStats, Summation, WspError,
+    ORIGIN_SEED,
+};
+use std::time::Instant;
+
+/// Row-major coordinates borrowed from the caller: point `i` is `data[i * nb_dim..(i + 1) * nb_dim]`.
+struct FlatPoints<'a> {
+    data: &'a [f64],
+    nb_dim: usize,
+    nb_points: usize,
+}
+
+impl<'a> FlatPoints<'a> {
+    fn get(&self, i: usize) -> &'a [f64] {
+        &self.data[i * self.nb_dim..(i + 1) * self.nb_dim]
+    }
+}
+
+impl Coordinates for FlatPoints<'_> {
+    fn nb_points(&self) -> usize {
+        self.nb_points
+    }
+
+    fn point(&self, i: usize) -> &[f64] {
+        self.get(i)
+    }
+}
+
+/// Set of candidate points borrowing its coordinates from the caller, with the KD-tree backend.
+/// It runs the classical algorithm (see [`crate::wsp`]) with the same origin and the same loop, and gives
+/// the same design as a [`crate::PointSet`] of the same points with [`crate::Backend::KdTree`].
+///
+/// # Example
+///
+/// ```
+/// // Buffer owned by the caller, e.g. a memory-mapped file
+/// let data: Vec<f64> = wsp::PointSet::init_from_random(1000, 2, 51).points.concat();
+/// let mut points = wsp::BorrowedPointSet::from_borrowed(&data, 1000, 2).unwrap();
+/// points.wsp(0.1);
+/// assert_eq!(points.get_remaining().len(), points.nb_active);
+/// ```
+pub struct BorrowedPointSet<'a> {
+    points: FlatPoints<'a>,
+    metric: Metric,
+    summation: Summation,
+    tree: KdTree,
+    /// If true, the point is still in the set. Stored with one bit per point
+    pub active: BitSet,
+    /// Number of active points in the set
+    pub nb_active: usize,
+    visited: BitSet,
+    fixed: Vec<bool>,
+    observer: Option<Box<RemovalObserver>>,
+}
+
+impl<'a> BorrowedPointSet<'a> {
+    /// Creates a set of `nb_points` points of dimension `nb_dim` whose coordinates are read in place
+    /// from `data`, point after point, with the Manhattan distance.
+    /// Returns an error if a coordinate is missing (NaN): the KD-tree requires all the coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The coordinates of the points, point after point.
+    /// * `nb_points` - The number of points.
+    /// * `nb_dim` - The dimension of the points.
+    pub fn from_borrowed(
+        data: &'a [f64],
+        nb_points: usize,
+        nb_dim: usize,
+    ) -> Result<BorrowedPointSet<'a>, WspError> {
+        assert_eq!(
+            Some(data.len()),
+            nb_points.checked_mul(nb_dim),
+            "Expected {} points of dimension {}, got {} coordinates",
+            nb_points,
+            nb_dim,
+            data.len()
+        );
+        if let Some(k) = data.iter().position(|x| x.is_nan()) {
+            return Err(WspError::MissingValue {
+                point: k / nb_dim,
+                dim: k % nb_dim,
+            });
+        }
+        let points = FlatPoints {
+            data,
+            nb_dim,
+            nb_points,
+        };
+        Ok(BorrowedPointSet {
+            tree: KdTree::new(&points),
+            points,
+            metric: Metric::Manhattan,
+            summation: Summation::default(),
+            active: BitSet::new(nb_points, true),
+            nb_active: nb_points,
+            visited: BitSet::new(nb_points, false),
+            fixed: vec![false; nb_points],
+            observer: None,
+        })
+    }
+
+    /// Returns the same set using another distance between the points.
+    pub fn with_metric(mut self, metric: Metric) -> BorrowedPointSet<'a> {
+        self.metric = metric;
+        self
+    }
+
+    /// Returns the same set summing the per-dimension terms of the distances with the given algorithm.
+    pub fn with_summation(mut self, summation: Summation) -> BorrowedPointSet<'a> {
+        self.summation = summation;
+        self
+    }
+
+    /// Marks the points at the given indexes as fixed, see [`crate::PointSet::set_fixed`].
+    pub fn set_fixed(&mut self, idxs: &[usize]) {
+        idxs.iter().for_each(|&i| self.fixed[i] = true);
+    }
+
+    /// Registers a function called each time the algorithm removes a point, see
+    /// [`crate::PointSet::set_observer`].
+    pub fn set_observer<F>(&mut self, observer: F)
+    where
+        F: FnMut(usize, usize, f64) + Send + Sync + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Returns the number of points, active or not.
+    pub fn nb_points(&self) -> usize {
+        self.points.nb_points
+    }
+
+    /// Returns the coordinates of the point at index `i`, borrowed from the caller's buffer.
+    pub fn point(&self, i: usize) -> &'a [f64] {
+        self.points.get(i)
+    }
+
+    /// Returns the active points, in increasing order of index, borrowed from the caller's buffer.
+    pub fn get_remaining(&self) -> Vec<&'a [f64]> {
+        self.active
+            .iter_ones()
+            .map(|i| self.points.get(i))
+            .collect()
+    }
+
+    /// Executes the WSP algorithm on the set, see [`crate::wsp`]. Returns the statistics of the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `d_min` - The desired minimal distance between all remaining points.
+    pub fn wsp(&mut self, d_min: f64) -> RunStats {
+        let mut stats = RunStats {
+            nb_runs: 1,
+            ..Default::default()
+        };
+        if self.points.nb_points == 0 {
+            return stats;
+        }
+        let start = Instant::now();
+        self.tree.sync(&self.active, &self.visited);
+        let origin = draw_origin(&self.active, &mut RngAlgorithm::Small.seeded(ORIGIN_SEED));
+        stats.setup_time = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        let mut walk = TreeWalk {
+            points: &self.points,
+            metric: self.metric,
+            summation: self.summation,
+            tree: &mut self.tree,
+            active: &mut self.active,
+            nb_active: &mut self.nb_active,
+            visited: &mut self.visited,
+            fixed: &self.fixed,
+            observer: self.observer.as_deref_mut(),
+        };
+        // Fixed points are used as origins first, as with a PointSet
+        for i in 0..self.points.nb_points {
+            if self.fixed[i] && !walk.visited[i] {
+                walk.run(|_| d_min, i, 0.0, &mut stats);
+            }
+        }
+        walk.run(|_| d_min, origin, 0.0, &mut stats);
+        stats.loop_time = start.elapsed().as_secs_f64();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wsp, Backend, PointSet};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_same_design_as_pointset() {
+        let candidates = PointSet::init_from_random(800, 3, 51).points;
+        let data = candidates.concat();
+        for metric in [Metric::Manhattan, Metric::Euclidean] {
+            let mut borrowed = BorrowedPointSet::from_borrowed(&data, 800, 3)
+                .unwrap()
+                .with_metric(metric);
+            let mut owned = PointSet::init_from_preset_with_backend(
+                candidates.clone(),
+                metric,
+                Backend::KdTree,
+            );
+            // Same fixed points and observed removals
+            borrowed.set_fixed(&[5, 500]);
+            owned.set_fixed(&[5, 500]);
+            let removed = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&removed);
+            borrowed
+                .set_observer(move |origin, victim, _| log.lock().unwrap().push((origin, victim)));
+            let expected = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&expected);
+            owned.set_observer(move |origin, victim, _| log.lock().unwrap().push((origin, victim)));
+
+            let stats = borrowed.wsp(0.2);
+            let expected_stats = wsp(&mut owned, 0.2);
+            assert_eq!(borrowed.active, owned.active);
+            assert_eq!(borrowed.nb_active, owned.nb_active);
+            assert!(borrowed.active[5] && borrowed.active[500]);
+            assert_eq!(*removed.lock().unwrap(), *expected.lock().unwrap());
+            assert_eq!(stats.nb_removed, expected_stats.nb_removed);
+            assert_eq!(stats.nb_origins, expected_stats.nb_origins);
+            // The remaining points point into the caller's buffer
+            let first = borrowed.get_remaining()[0];
+            assert!(data.as_ptr_range().contains(&first.as_ptr()));
+        }
+    }
+
+    #[test]
+    fn test_missing_value() {
+        let data = [0.0, 1.0, 0.5, f64::NAN];
+        assert_eq!(
+            BorrowedPointSet::from_borrowed(&data, 2, 2).err(),
+            Some(WspError::MissingValue { point: 1, dim: 1 })
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Checkpoints of the adaptive algorithm, so that a killed run can be resumed
+//! instead of restarting from zero.
+//! A checkpoint file starts with the state of the search and the best design found so far, followed by the index
+//! of the PointSet (see [`PointSet::save_index`]). The index is only written once; the state
+//! (whose size only depends on the number of points) is overwritten in place after each iteration.
+
+use crate::binary::{expect_magic, read_f64, read_u64, read_usize, write_f64, write_u64};
+use crate::{
+    adaptive_loop, AdaptiveSearch, BitSet, PointSet, RunStats, SelectionCriterion, Snapshot,
+    WspError,
+};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"WSPCKP06";
+
+impl AdaptiveSearch {
+    fn write<W: Write>(&self, wrt: &mut W) -> Result<(), Box<dyn Error>> {
+        wrt.write_all(CHECKPOINT_MAGIC)?;
+        write_u64(wrt, self.obj_nb as u64)?;
+        write_f64(wrt, self.d_min)?;
+        write_f64(wrt, self.d_max)?;
+        write_f64(wrt, self.d_search)?;
+        write_u64(wrt, self.iter as u64)?;
+        write_f64(wrt, self.best_distance)?;
+        write_u64(wrt, self.best_difference_active as u64)?;
+        let (code, exponent) = match self.criterion {
+            SelectionCriterion::MaxActive => (0, 0.0),
+            SelectionCriterion::MaxMinDistance => (1, 0.0),
+            SelectionCriterion::MinPhiP(p) => (2, p),
+        };
+        write_u64(wrt, code)?;
+        write_f64(wrt, exponent)?;
+        write_f64(wrt, self.best_score)?;
+        write_u64(wrt, self.stats.nb_removed as u64)?;
+        write_u64(wrt, self.stats.nb_origins as u64)?;
+        write_u64(wrt, self.stats.nb_iterations as u64)?;
+        write_u64(wrt, self.stats.nb_runs as u64)?;
+        write_f64(wrt, self.stats.setup_time)?;
+        write_f64(wrt, self.stats.loop_time)?;
+        // The best design is restored at the end of the search
+        match self.best_design.as_ref() {
+            Some(best) => {
+                write_u64(wrt, best.active.len() as u64)?;
+                write_flags(wrt, &best.active)?;
+                write_flags(wrt, &best.visited)?;
+            }
+            None => write_u64(wrt, 0)?,
+        }
+        Ok(())
+    }
+
+    /// Reads the state of the search. The fixed flags of the best design are not stored,
+    /// and must be set from the PointSet.
+    fn read<R: Read>(rdr: &mut R) -> Result<AdaptiveSearch, Box<dyn Error>> {
+        expect_magic(rdr, CHECKPOINT_MAGIC)?;
+        let mut search = AdaptiveSearch {
+            obj_nb: read_usize(rdr)?,
+            d_min: read_f64(rdr)?,
+            d_max: read_f64(rdr)?,
+            d_search: read_f64(rdr)?,
+            iter: read_usize(rdr)?,
+            best_distance: read_f64(rdr)?,
+            best_difference_active: read_usize(rdr)?,
+            best_design: None,
+            criterion: match (read_u64(rdr)?, read_f64(rdr)?) {
+                (0, _) => SelectionCriterion::MaxActive,
+                (1, _) => SelectionCriterion::MaxMinDistance,
+                (2, p) => SelectionCriterion::MinPhiP(p),
+                (code, _) => {
+                    return Err(WspError::InvalidFile(format!(
+                        "unknown selection criterion {}",
+                        code
+                    ))
+                    .into())
+                }
+            },
+            best_score: read_f64(rdr)?,
+            stats: RunStats {
+                nb_removed: read_usize(rdr)?,
+                nb_origins: read_usize(rdr)?,
+                nb_iterations: read_usize(rdr)?,
+                nb_runs: read_usize(rdr)?,
+                setup_time: read_f64(rdr)?,
+                loop_time: read_f64(rdr)?,
+            },
+        };
+        let nb_best = read_usize(rdr)?;
+        if nb_best > 0 {
+            search.best_design = Some(Snapshot {
+                active: read_flags(rdr, nb_best)?,
+                visited: read_flags(rdr, nb_best)?,
+                fixed: BitSet::default(),
+            });
+        }
+        Ok(search)
+    }
+}
+
+/// Writes one byte per flag.
+fn write_flags<W: Write>(wrt: &mut W, flags: &BitSet) -> io::Result<()> {
+    let bytes: Vec<u8> = flags.iter().map(|a| a as u8).collect();
+    wrt.write_all(&bytes)
+}
+
+fn read_flags<R: Read>(rdr: &mut R, len: usize) -> io::Result<BitSet> {
+    let mut bytes = vec![0u8; len];
+    rdr.read_exact(&mut bytes)?;
+    Ok(bytes.into_iter().map(|a| a != 0).collect())
+}
+
+/// Writes the state of the search in the checkpoint file `filepath`.
+/// The index of the PointSet is only written with the first checkpoint.
+fn write_checkpoint(
+    set: &mut PointSet,
+    search: &AdaptiveSearch,
+    filepath: &str,
+    with_index: bool,
+) -> Result<(), Box<dyn Error>> {
+    if with_index {
+        let mut wrt = BufWriter::new(File::create(filepath)?);
+        search.write(&mut wrt)?;
+        set.write_index(&mut wrt)?;
+        wrt.flush()?;
+    } else {
+        let mut file = OpenOptions::new().write(true).open(filepath)?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut wrt = BufWriter::new(file);
+        search.write(&mut wrt)?;
+        wrt.flush()?;
+    }
+    Ok(())
+}
+
+fn adaptive_with_checkpoints(
+    set: &mut PointSet,
+    search: &mut AdaptiveSearch,
+    verbose: bool,
+    filepath: &str,
+    mut with_index: bool,
+) -> Result<RunStats, Box<dyn Error>> {
+    adaptive_loop(set, search, verbose, |set, search| {
+        write_checkpoint(set, search, filepath, with_index)?;
+        with_index = false;
+        Ok(())
+    })?;
+    Ok(search.stats)
+}
+
+/// Same as [`crate::adaptive_wsp`], but stores the state of the search in a checkpoint file
+/// after each iteration, so that the run can be resumed with [`resume_adaptive_wsp`] if it is interrupted.
+/// The checkpoint file also contains the points, the distance matrix and the sorted neighbours.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `obj_nb` - The desired number of points remaining active in the set after the algorithm.
+/// * `verbose` - Print running information about the iterations of the algorithm.
+/// * `filepath` - The path to the checkpoint file.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(500, 10, 51);
+/// wsp::adaptive_wsp_with_checkpoint(&mut points, 50, false, "wsp_state.bin").unwrap();
+///
+/// // Continues from the last checkpoint, e.g. after the process was killed
+/// let (resumed, _stats) = wsp::resume_adaptive_wsp("wsp_state.bin", false).unwrap();
+/// assert_eq!(resumed.active, points.active);
+/// # std::fs::remove_file("wsp_state.bin").unwrap();
+/// ```
+pub fn adaptive_wsp_with_checkpoint(
+    set: &mut PointSet,
+    obj_nb: usize,
+    verbose: bool,
+    filepath: &str,
+) -> Result<RunStats, Box<dyn Error>> {
+    let mut search = AdaptiveSearch::new(set, obj_nb, SelectionCriterion::default());
+    adaptive_with_checkpoints(set, &mut search, verbose, filepath, true)
+}
+
+/// Resumes an adaptive run from a checkpoint file written by [`adaptive_wsp_with_checkpoint`].
+/// The run keeps updating the checkpoint file.
+/// Returns the resulting PointSet and the statistics accumulated over the whole run.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the checkpoint file.
+/// * `verbose` - Print running information about the iterations of the algorithm.
+pub fn resume_adaptive_wsp(
+    filepath: &str,
+    verbose: bool,
+) -> Result<(PointSet, RunStats), Box<dyn Error>> {
+    let mut rdr = BufReader::new(File::open(filepath)?);
+    // The PointSet is reset before each checkpoint: all its points are active
+    let mut search = AdaptiveSearch::read(&mut rdr)?;
+    let mut set = PointSet::read_index(&mut rdr)?;
+    drop(rdr);
+    if let Some(best) = search.best_design.as_mut() {
+        if best.active.len() != set.points.len() {
+            return Err(WspError::InvalidFile(format!(
+                "{} active flags for {} points",
+                best.active.len(),
+                set.points.len()
+            ))
+            .into());
+        }
+        best.fixed = set.fixed.iter().copied().collect();
+    }
+
+    let stats = adaptive_with_checkpoints(&mut set, &mut search, verbose, filepath, false)?;
+    Ok((set, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{adaptive_wsp, RngAlgorithm};
+
+    #[test]
+    fn test_resume_from_any_iteration() {
+        let filepath = "wsp_test_checkpoint.bin";
+        let points = PointSet::init_from_random(400, 4, 51).points;
+        // The origin of each iteration depends on the seed and the generator
+        let init = || {
+            PointSet::init_from_preset(points.clone())
+                .with_seed(12345)
+                .with_rng_algorithm(RngAlgorithm::Pcg64)
+        };
+        let mut expected = init();
+        let expected_stats = adaptive_wsp(&mut expected, 37, false);
+
+        // Interrupt the run after a given number of checkpoints
+        for nb_checkpoints in 1..4 {
+            let mut set = init();
+            let mut search = AdaptiveSearch::new(&set, 37, SelectionCriterion::default());
+            let mut count = 0;
+            let interrupted = adaptive_loop(&mut set, &mut search, false, |set, search| {
+                write_checkpoint(set, search, filepath, count == 0)?;
+                count += 1;
+                if count == nb_checkpoints {
+                    return Err("interrupted".into());
+                }
+                Ok(())
+            });
+            assert!(interrupted.is_err());
+
+            let (resumed, stats) = resume_adaptive_wsp(filepath, false).unwrap();
+            assert_eq!(resumed.rng_algorithm(), RngAlgorithm::Pcg64);
+            assert_eq!(resumed.origin_seed, expected.origin_seed);
+            assert_eq!(resumed.active, expected.active);
+            assert_eq!(stats.nb_runs, expected_stats.nb_runs);
+        }
+        std::fs::remove_file(filepath).unwrap();
+    }
+}
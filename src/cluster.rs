@@ -0,0 +1,204 @@
+//! Cluster-aware WSP: the candidate set is first partitioned with k-means,
+//! then WSP runs independently in each cluster. This preserves the multimodal
+//! structure of the candidate set that a single global minimal distance would erase.
+
+use crate::{adaptive_wsp, PointSet, RunStats};
+use rand::rngs::SmallRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+
+/// Maximal number of iterations of the k-means algorithm.
+const KMEANS_MAX_ITER: usize = 100;
+
+/// Partitions the points in `k` clusters using the k-means (Lloyd) algorithm.
+/// The initial centroids are `k` distinct points chosen at random.
+/// Returns the cluster of each point.
+///
+/// The clusters always use the Euclidean distance, whatever the metric of the points.
+/// Missing coordinates (NaN) are skipped, both in the distances and in the centroids.
+///
+/// # Arguments
+///
+/// * `points` - The points to partition.
+/// * `k` - The number of clusters. It is capped to the number of points.
+/// * `seed` - The seed used to choose the initial centroids.
+///
+/// # Example
+///
+/// ```
+/// let points = vec![vec![0.0, 0.0], vec![0.1, 0.0], vec![5.0, 5.0], vec![5.1, 5.0]];
+/// let labels = wsp::kmeans(&points, 2, 51);
+/// assert_eq!(labels[0], labels[1]);
+/// assert_eq!(labels[2], labels[3]);
+/// assert_ne!(labels[0], labels[2]);
+/// ```
+pub fn kmeans(points: &[Vec<f64>], k: usize, seed: u64) -> Vec<usize> {
+    kmeans_with_rng(points, k, &mut SmallRng::seed_from_u64(seed))
+}
+
+/// Same as [`kmeans`], choosing the initial centroids with a caller-supplied random number generator.
+pub fn kmeans_with_rng<R: Rng + ?Sized>(points: &[Vec<f64>], k: usize, rng: &mut R) -> Vec<usize> {
+    let k = k.min(points.len());
+    let mut labels = vec![0; points.len()];
+    if k == 0 {
+        return labels;
+    }
+
+    let mut centroids: Vec<Vec<f64>> = sample(rng, points.len(), k)
+        .iter()
+        .map(|i| points[i].clone())
+        .collect();
+
+    for _ in 0..KMEANS_MAX_ITER {
+        // Assignment step
+        let mut changed = false;
+        for (label, point) in labels.iter_mut().zip(points.iter()) {
+            // A centroid without any known coordinate in common with the point (NaN distance) comes last
+            let closest = (0..k)
+                .min_by(|&a, &b| {
+                    known_distance_sq(point, &centroids[a])
+                        .total_cmp(&known_distance_sq(point, &centroids[b]))
+                })
+                .unwrap();
+            if closest != *label {
+                *label = closest;
+                changed = true;
+            }
+        }
+
+        // Update step, per dimension. The coordinates without any known value in the cluster
+        // (e.g. empty clusters) keep their previous value
+        let nb_dim = points[0].len();
+        let mut sums = vec![vec![0.0; nb_dim]; k];
+        let mut counts = vec![vec![0usize; nb_dim]; k];
+        for (&label, point) in labels.iter().zip(points.iter()) {
+            for (dim, x) in point.iter().enumerate().filter(|(_, x)| !x.is_nan()) {
+                sums[label][dim] += x;
+                counts[label][dim] += 1;
+            }
+        }
+        for c in 0..k {
+            for dim in 0..nb_dim {
+                if counts[c][dim] > 0 {
+                    centroids[c][dim] = sums[c][dim] / counts[c][dim] as f64;
+                }
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
+
+/// Squared Euclidean distance on the coordinates known for both points, renormalized as if all
+/// coordinates were known. Returns NaN if the points do not share any known coordinate.
+fn known_distance_sq(p1: &[f64], p2: &[f64]) -> f64 {
+    let (sum, nb_known) = p1
+        .iter()
+        .zip(p2.iter())
+        .filter(|(x1, x2)| !x1.is_nan() && !x2.is_nan())
+        .fold((0.0, 0), |(sum, n), (x1, x2)| {
+            (sum + (x1 - x2) * (x1 - x2), n + 1)
+        });
+    match nb_known {
+        0 => f64::NAN,
+        n if n == p1.len() => sum,
+        n => sum * p1.len() as f64 / n as f64,
+    }
+}
+
+/// Runs the adaptive WSP algorithm independently in each of the `k` clusters of the PointSet.
+/// Each cluster receives a budget of active points proportional to its size, such that the
+/// resulting set contains (approximately) `obj_nb` active points in total.
+/// The clusters are computed with the Euclidean distance (see [`kmeans`]), and WSP runs in each
+/// cluster with the metric of the PointSet.
+/// The returned statistics are accumulated over all the clusters.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `k` - The number of clusters computed with k-means.
+/// * `obj_nb` - The desired number of points remaining active in the set after the algorithm.
+/// * `seed` - The seed used for the k-means initialisation, drawn with the generator algorithm of the PointSet
+///   (see [`PointSet::with_rng_algorithm`]).
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 5, 51);
+/// wsp::cluster_wsp(&mut points, 4, 100, 51);
+/// ```
+pub fn cluster_wsp(set: &mut PointSet, k: usize, obj_nb: usize, seed: u64) -> RunStats {
+    let labels = kmeans_with_rng(&set.points, k, &mut set.rng_algorithm.seeded(seed));
+    let nb_points = set.points.len();
+
+    set.reset_reseach_params();
+    let mut stats = RunStats::default();
+    for c in 0..k.min(nb_points) {
+        let idxs: Vec<usize> = (0..nb_points).filter(|&i| labels[i] == c).collect();
+        if idxs.is_empty() {
+            continue;
+        }
+
+        // Proportional budget, with at least one point per cluster
+        let budget = ((obj_nb * idxs.len()) as f64 / nb_points as f64).round() as usize;
+        let budget = budget.clamp(1, idxs.len());
+        if budget == idxs.len() {
+            idxs.iter().for_each(|&i| set.visited.set(i, true));
+            continue;
+        }
+
+        let mut cluster_set = set.subset(&idxs);
+        stats += adaptive_wsp(&mut cluster_set, budget, false);
+        for (sub_i, &i) in idxs.iter().enumerate() {
+            set.active.set(i, cluster_set.active[sub_i]);
+            set.visited.set(i, cluster_set.visited[sub_i]);
+        }
+    }
+    set.nb_active = set.active.count_ones();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster_wsp_keeps_all_modes() {
+        // Two dense clusters of different sizes, far from each other
+        let mut points: Vec<Vec<f64>> = Vec::new();
+        let random = PointSet::init_from_random(300, 2, 51);
+        for (i, point) in random.points.iter().enumerate() {
+            let offset = if i < 250 { 0.0 } else { 10.0 };
+            points.push(point.iter().map(|x| x * 0.1 + offset).collect());
+        }
+        let mut pointset = PointSet::init_from_preset(points);
+        cluster_wsp(&mut pointset, 2, 30, 51);
+
+        let nb_far = (250..300).filter(|&i| pointset.active[i]).count();
+        assert!(nb_far > 0);
+        assert!(pointset.nb_active.abs_diff(30) <= 3);
+        assert_eq!(pointset.nb_active, pointset.active.count_ones());
+    }
+
+    #[test]
+    fn test_kmeans_missing_values() {
+        let mut points: Vec<Vec<f64>> = PointSet::init_from_random(100, 2, 51)
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| {
+                let offset = if i < 50 { 0.0 } else { 10.0 };
+                point.iter().map(|x| x + offset).collect()
+            })
+            .collect();
+        points[3][0] = f64::NAN;
+        points[60][1] = f64::NAN;
+        let labels = kmeans(&points, 2, 51);
+        assert!(labels[..50].iter().all(|&l| l == labels[0]));
+        assert!(labels[50..].iter().all(|&l| l == labels[50]));
+        assert_ne!(labels[0], labels[50]);
+    }
+}
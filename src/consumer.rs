@@ -0,0 +1,138 @@
+//! Hand-over of the selected design to other crates, e.g. the trainer of a surrogate model (Gaussian
+//! process, kriging...) in a Bayesian optimization loop, without writing intermediate files.
+
+use crate::{OutputScale, PointSet};
+
+/// Receiver of a design: the coordinates `x` of the selected points, one row per point, and the `indices`
+/// of these points among the candidates of the PointSet, in the same order.
+///
+/// Closures taking `(&[Vec<f64>], &[usize])` are consumers. Crates storing their samples in a contiguous
+/// row-major matrix can be fed through [`RowMajor`].
+pub trait DesignConsumer {
+    /// Value returned once the design is consumed, e.g. a trained model.
+    type Output;
+
+    /// Receives the design.
+    fn consume(&mut self, x: &[Vec<f64>], indices: &[usize]) -> Self::Output;
+}
+
+impl<F, T> DesignConsumer for F
+where
+    F: FnMut(&[Vec<f64>], &[usize]) -> T,
+{
+    type Output = T;
+
+    fn consume(&mut self, x: &[Vec<f64>], indices: &[usize]) -> T {
+        self(x, indices)
+    }
+}
+
+/// Adapter passing the design as a row-major matrix, the layout expected by most matrix crates
+/// (e.g. `ndarray::Array2::from_shape_vec((nb_points, nb_dim), data)`). The wrapped closure receives
+/// the coordinates point after point, the number of points, the dimension, and the indices of the points.
+///
+/// # Example
+///
+/// ```
+/// use wsp::{OutputScale, RowMajor};
+///
+/// let mut points = wsp::PointSet::init_from_random(1000, 3, 51);
+/// wsp::wsp(&mut points, 0.3);
+/// let shape = points.feed_design(
+///     OutputScale::Original,
+///     &mut RowMajor(|data: &[f64], nb_points, nb_dim, _indices: &[usize]| {
+///         assert_eq!(data.len(), nb_points * nb_dim);
+///         (nb_points, nb_dim)
+///     }),
+/// );
+/// assert_eq!(shape, (points.nb_active, 3));
+/// ```
+pub struct RowMajor<F>(pub F);
+
+impl<F, T> DesignConsumer for RowMajor<F>
+where
+    F: FnMut(&[f64], usize, usize, &[usize]) -> T,
+{
+    type Output = T;
+
+    fn consume(&mut self, x: &[Vec<f64>], indices: &[usize]) -> T {
+        let nb_dim = x.first().map_or(0, |p| p.len());
+        let data: Vec<f64> = x.iter().flatten().copied().collect();
+        (self.0)(&data, x.len(), nb_dim, indices)
+    }
+}
+
+impl PointSet {
+    /// Hands the active points of the PointSet over to a [`DesignConsumer`], in increasing order of index,
+    /// and returns what the consumer returns.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The representation of the coordinates. See [`PointSet::get_remaining_in`].
+    /// * `consumer` - The receiver of the design.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+    /// wsp::wsp(&mut points, 0.1);
+    ///
+    /// // Stand-in for the training of a surrogate model on the evaluated design
+    /// let mean = points.feed_design(wsp::OutputScale::Original, &mut |x: &[Vec<f64>], _: &[usize]| {
+    ///     x.iter().map(|p| p[0]).sum::<f64>() / x.len() as f64
+    /// });
+    /// assert!(mean > 0.0 && mean < 1.0);
+    /// ```
+    pub fn feed_design<C: DesignConsumer + ?Sized>(
+        &self,
+        scale: OutputScale,
+        consumer: &mut C,
+    ) -> C::Output {
+        let indices: Vec<usize> = self.active.iter_ones().collect();
+        consumer.consume(&self.get_remaining_in(scale), &indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsp;
+
+    /// Consumer keeping a copy of the design, as a surrogate-model trainer would.
+    #[derive(Default)]
+    struct Recorder {
+        samples: Vec<(usize, Vec<f64>)>,
+    }
+
+    impl DesignConsumer for Recorder {
+        type Output = usize;
+
+        fn consume(&mut self, x: &[Vec<f64>], indices: &[usize]) -> usize {
+            self.samples
+                .extend(indices.iter().copied().zip(x.iter().cloned()));
+            x.len()
+        }
+    }
+
+    #[test]
+    fn test_feed_design() {
+        let mut points = PointSet::init_from_random(800, 3, 51);
+        wsp(&mut points, 0.3);
+
+        let mut recorder = Recorder::default();
+        let nb_points = points.feed_design(OutputScale::Original, &mut recorder);
+        assert_eq!(nb_points, points.nb_active);
+        for (i, x) in recorder.samples.iter() {
+            assert!(points.active[*i]);
+            assert_eq!(x, &points.points[*i]);
+        }
+
+        let mut flat = RowMajor(|data: &[f64], nb_points, nb_dim, indices: &[usize]| {
+            (data.to_vec(), nb_points, nb_dim, indices.to_vec())
+        });
+        let (data, nb_points, nb_dim, indices) =
+            points.feed_design(OutputScale::Original, &mut flat);
+        assert_eq!((nb_points, nb_dim), (points.nb_active, 3));
+        assert_eq!(data[3..6], points.points[indices[1]][..]);
+    }
+}
@@ -0,0 +1,57 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors that may be returned when building or processing a PointSet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WspError {
+    /// The point at the given index does not lie on the unit simplex.
+    NotOnSimplex(usize),
+    /// The point at the given index cannot be projected on the unit hypersphere.
+    NotOnSphere(usize),
+    /// Two points, or two sets of points, do not have the same dimension.
+    DimensionMismatch { expected: usize, found: usize },
+    /// The coordinate `dim` of the point at index `point` is missing (NaN).
+    MissingValue { point: usize, dim: usize },
+    /// The given dimension does not have any known (non-NaN) value.
+    NoKnownValue(usize),
+    /// The points at the given indexes do not have any known coordinate in common.
+    NoCommonCoordinate(usize, usize),
+    /// The content of a file does not follow the expected format.
+    InvalidFile(String),
+}
+
+impl fmt::Display for WspError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WspError::NotOnSimplex(idx) => {
+                write!(f, "point #{} does not lie on the unit simplex", idx)
+            }
+            WspError::NotOnSphere(idx) => {
+                write!(
+                    f,
+                    "point #{} cannot be projected on the unit hypersphere",
+                    idx
+                )
+            }
+            WspError::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected points of dimension {}, found dimension {}",
+                expected, found
+            ),
+            WspError::MissingValue { point, dim } => {
+                write!(f, "coordinate {} of point #{} is missing", dim, point)
+            }
+            WspError::NoKnownValue(dim) => {
+                write!(f, "dimension {} does not have any known value", dim)
+            }
+            WspError::NoCommonCoordinate(i, j) => write!(
+                f,
+                "points #{} and #{} do not have any known coordinate in common",
+                i, j
+            ),
+            WspError::InvalidFile(reason) => write!(f, "invalid file: {}", reason),
+        }
+    }
+}
+
+impl Error for WspError {}
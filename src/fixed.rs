@@ -0,0 +1,123 @@
+//! Fixed-point determinism mode. The comparisons of the distances with the minimal distance decide the
+//! design, and a distance computed a few ulps apart on another architecture or with another compiler
+//! may flip one of them. Snapping the coordinates on a grid of step `2^-bits` makes the l1 and l2
+//! distances exact: the differences of coordinates, their absolute values and their squares are
+//! multiples of the step (or of its square) that fit in the mantissa, so their sums do not depend on the
+//! order or on the summation algorithm. The only remaining rounding, the square root of the l2 distance,
+//! is correctly rounded by IEEE 754, so the same candidates give the same design on x86, ARM and WASM
+//! as long as the origins come from a portable generator (see [`crate::RngAlgorithm`]).
+
+use crate::PointSet;
+
+/// Largest number of fractional bits of the fixed-point coordinates.
+pub const MAX_FRACTIONAL_BITS: u32 = 52;
+
+/// Returns `x` rounded to the closest multiple of `2^-bits`. Missing values stay missing.
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(wsp::snap_to_fixed_point(0.3, 2), 0.25);
+/// assert_eq!(wsp::snap_to_fixed_point(-0.9, 1), -1.0);
+/// ```
+pub fn snap_to_fixed_point(x: f64, bits: u32) -> f64 {
+    assert!(
+        bits <= MAX_FRACTIONAL_BITS,
+        "At most {} fractional bits, got {}",
+        MAX_FRACTIONAL_BITS,
+        bits
+    );
+    // Multiplying and dividing by a power of two are exact
+    let scale = (1u64 << bits) as f64;
+    (x * scale).round() / scale
+}
+
+impl PointSet {
+    /// Returns the same PointSet with its coordinates snapped to the closest multiples of `2^-bits`, so that
+    /// the design only depends on the candidates and not on the platform computing the distances.
+    /// The internal structures are rebuilt, and the state of the points (active, fixed...) is kept.
+    /// The design is made of the snapped candidates, within half a step of the original ones.
+    ///
+    /// The distances are exact while the l1 distances divided by the step (the sums of squared
+    /// differences divided by the squared step with the l2 distance) stay below `2^53`: e.g. with
+    /// 20 bits, up to 8192 dimensions in the unit cube with the l2 distance. The geodesic distance
+    /// goes through `acos`, whose implementation differs between platforms, and is not covered.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The number of fractional bits of the coordinates, at most [`MAX_FRACTIONAL_BITS`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 10, 51).with_fixed_point(20);
+    /// assert_eq!(points.fixed_point(), Some(20));
+    /// wsp::wsp(&mut points, 1.0);
+    /// ```
+    pub fn with_fixed_point(mut self, bits: u32) -> PointSet {
+        if self.fixed_point == Some(bits) {
+            return self;
+        }
+        for point in self.points.iter_mut() {
+            point
+                .iter_mut()
+                .for_each(|x| *x = snap_to_fixed_point(*x, bits));
+        }
+        let (backend, summation) = (self.backend, self.summation);
+        let mut p = self.rebuild(backend, summation);
+        p.fixed_point = Some(bits);
+        p
+    }
+
+    /// Returns the number of fractional bits of the fixed-point coordinates, if any.
+    pub fn fixed_point(&self) -> Option<u32> {
+        self.fixed_point
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wsp, Backend, Metric, Summation};
+
+    #[test]
+    fn test_fixed_point() {
+        let candidates = PointSet::init_from_random(600, 40, 51).points;
+        for (metric, d_min) in [(Metric::Manhattan, 10.0), (Metric::Euclidean, 2.0)] {
+            let designs: Vec<_> = [Backend::Dense, Backend::KdTree, Backend::Grid]
+                .into_iter()
+                .flat_map(|backend| [(backend, Summation::Naive), (backend, Summation::Neumaier)])
+                .map(|(backend, summation)| {
+                    let mut points = PointSet::init_from_preset_with_backend(
+                        candidates.clone(),
+                        metric,
+                        backend,
+                    )
+                    .with_summation(summation)
+                    .with_fixed_point(16);
+                    // Exact distances: the same whatever the order of the terms
+                    let (p, q) = (&points.points[3], &points.points[8]);
+                    let reversed: Vec<Vec<f64>> = [p, q]
+                        .iter()
+                        .map(|x| x.iter().rev().copied().collect())
+                        .collect();
+                    assert_eq!(
+                        metric.distance_with(p, q, Summation::Naive),
+                        metric.distance_with(&reversed[0], &reversed[1], Summation::Naive)
+                    );
+                    assert_eq!(points.distance(3, 8), metric.distance(p, q));
+                    wsp(&mut points, d_min);
+                    points.active.to_vec()
+                })
+                .collect();
+            assert!(designs.windows(2).all(|w| w[0] == w[1]));
+        }
+
+        let mut points =
+            PointSet::init_from_preset(vec![vec![0.1, 0.3], vec![0.7, 0.2]]).with_fixed_point(3);
+        assert_eq!(points.points, vec![vec![0.125, 0.25], vec![0.75, 0.25]]);
+        wsp(&mut points, 0.1);
+        assert_eq!(points.nb_active, 2);
+        assert!(snap_to_fixed_point(f64::NAN, 3).is_nan());
+    }
+}
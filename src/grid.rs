@@ -0,0 +1,465 @@
+//! Matrix-free backend of the WSP algorithm for low dimensions, based on a uniform grid.
+//! The points are bucketed in cubic cells whose side is at least the minimal distance, so that the
+//! neighbours of an origin are found by scanning the adjacent cells only. Each cell tracks its
+//! number of available points, so that the search of the next origin skips the cleared cells.
+
+use crate::{BitSet, Metric, PointSet, RunStats, Summation};
+
+/// Relative margin of the distance bounds of the cells, covering the rounding errors of the cell coordinates.
+const BOUND_MARGIN: f64 = 1e-9;
+
+pub(crate) struct Grid {
+    /// Lower corner of the grid
+    lower: Vec<f64>,
+    /// Side of the cells, and the minimal side requested
+    cell_size: f64,
+    min_cell_size: f64,
+    /// Absolute margin of the distance bounds of the cells
+    slack: f64,
+    /// Number of cells along each dimension
+    shape: Vec<usize>,
+    /// Permutation of the point indexes, sorted by cell. The points of cell `c` are `idxs[start[c]..start[c + 1]]`
+    idxs: Vec<usize>,
+    start: Vec<usize>,
+    /// Cell containing each point
+    cell_of: Vec<usize>,
+    /// Number of available points in each cell
+    available: Vec<usize>,
+    /// Cells containing available points, and position of each cell in `live`
+    live: Vec<usize>,
+    live_pos: Vec<usize>,
+}
+
+impl Grid {
+    /// Builds a grid whose cells are at least `min_cell_size` wide. The cells are enlarged if needed,
+    /// such that there are no more cells than points.
+    pub(crate) fn new(points: &[Vec<f64>], min_cell_size: f64) -> Grid {
+        let nb_dim = points.first().map_or(0, |p| p.len());
+        let mut lower = vec![f64::MAX; nb_dim];
+        let mut upper = vec![f64::MIN; nb_dim];
+        for point in points.iter() {
+            for (d, &x) in point.iter().enumerate() {
+                lower[d] = lower[d].min(x);
+                upper[d] = upper[d].max(x);
+            }
+        }
+        let shape_of = |cell_size: f64| -> Vec<usize> {
+            lower
+                .iter()
+                .zip(upper.iter())
+                .map(|(l, u)| ((u - l).max(0.0) / cell_size).floor() as usize + 1)
+                .collect()
+        };
+        let mut cell_size = min_cell_size;
+        let mut shape = shape_of(cell_size);
+        while shape.iter().map(|&s| s as f64).product::<f64>() > points.len().max(1) as f64 {
+            cell_size *= 1.25;
+            shape = shape_of(cell_size);
+        }
+        let nb_cells = shape.iter().product();
+        let magnitude = lower
+            .iter()
+            .chain(upper.iter())
+            .fold(0.0f64, |max, x| max.max(x.abs()));
+
+        let mut grid = Grid {
+            lower,
+            cell_size,
+            min_cell_size,
+            slack: BOUND_MARGIN * (cell_size + magnitude),
+            shape,
+            idxs: Vec::new(),
+            start: vec![0; nb_cells + 1],
+            cell_of: Vec::new(),
+            available: Vec::new(),
+            live: Vec::new(),
+            live_pos: vec![0; nb_cells],
+        };
+        grid.cell_of = points.iter().map(|p| grid.cell(&grid.key(p))).collect();
+        // Counting sort of the points by cell
+        for &cell in grid.cell_of.iter() {
+            grid.start[cell + 1] += 1;
+        }
+        for cell in 0..nb_cells {
+            grid.start[cell + 1] += grid.start[cell];
+        }
+        let mut next = grid.start.clone();
+        grid.idxs = vec![0; points.len()];
+        for (i, &cell) in grid.cell_of.iter().enumerate() {
+            grid.idxs[next[cell]] = i;
+            next[cell] += 1;
+        }
+        grid.available = vec![0; nb_cells];
+        grid
+    }
+
+    pub(crate) fn min_cell_size(&self) -> f64 {
+        self.min_cell_size
+    }
+
+    /// Integer coordinates of the cell containing `point`.
+    fn key(&self, point: &[f64]) -> Vec<usize> {
+        point
+            .iter()
+            .zip(self.lower.iter())
+            .zip(self.shape.iter())
+            .map(|((x, lower), &size)| {
+                (((x - lower) / self.cell_size).floor() as usize).min(size - 1)
+            })
+            .collect()
+    }
+
+    /// Linear index of the cell of integer coordinates `key`, the first dimension varying fastest.
+    fn cell(&self, key: &[usize]) -> usize {
+        key.iter()
+            .zip(self.shape.iter())
+            .rev()
+            .fold(0, |cell, (&k, &size)| cell * size + k)
+    }
+
+    /// Integer coordinates of the cell of linear index `cell`.
+    fn decode(&self, mut cell: usize, key: &mut [usize]) {
+        for (k, &size) in key.iter_mut().zip(self.shape.iter()) {
+            *k = cell % size;
+            cell /= size;
+        }
+    }
+
+    /// Lower bound of the distance between a point and the points of a cell at a Chebyshev
+    /// distance of `offset` cells from its own cell.
+    fn ring_bound(&self, metric: Metric, offset: usize) -> f64 {
+        let gap = offset.saturating_sub(1) as f64 * self.cell_size - self.slack;
+        metric.axis_lower_bound(gap.max(0.0))
+    }
+
+    /// Lower bound of the distance between `query` and the points of the cell of integer coordinates `key`.
+    fn cell_bound(&self, metric: Metric, query: &[f64], key: &[usize]) -> f64 {
+        let gaps = query
+            .iter()
+            .zip(self.lower.iter())
+            .zip(key.iter())
+            .map(|((&q, &lower), &k)| {
+                let start = lower + k as f64 * self.cell_size;
+                ((start - q).max(q - start - self.cell_size) - self.slack).max(0.0)
+            });
+        match metric {
+            Metric::Manhattan => gaps.sum(),
+            Metric::Euclidean => gaps.map(|g| g * g).sum::<f64>().sqrt(),
+            Metric::Geodesic => metric.axis_lower_bound(gaps.fold(0.0, f64::max)),
+        }
+    }
+
+    /// Calls `f` on each cell at a Chebyshev distance between `min_offset` and `max_offset`
+    /// cells from the cell `center`, within the bounds of the grid, with its linear index and its key.
+    fn for_each_cell<F: FnMut(usize, &[usize])>(
+        &self,
+        center: &[usize],
+        min_offset: usize,
+        max_offset: usize,
+        mut f: F,
+    ) {
+        if center.is_empty() {
+            return f(0, center);
+        }
+        let low: Vec<usize> = center
+            .iter()
+            .map(|c| c.saturating_sub(max_offset))
+            .collect();
+        let high: Vec<usize> = center
+            .iter()
+            .zip(self.shape.iter())
+            .map(|(c, size)| c.saturating_add(max_offset).min(size - 1))
+            .collect();
+        let mut key = low.clone();
+        loop {
+            // Rows along the first dimension
+            let rest = key[1..]
+                .iter()
+                .zip(center[1..].iter())
+                .map(|(k, c)| k.abs_diff(*c))
+                .max()
+                .unwrap_or(0);
+            let row = self.cell(&key);
+            for k in low[0]..=high[0] {
+                if rest.max(k.abs_diff(center[0])) >= min_offset {
+                    key[0] = k;
+                    f(row + k - low[0], &key);
+                }
+            }
+            key[0] = low[0];
+            // Next row of the box
+            let mut d = 1;
+            loop {
+                if d == key.len() {
+                    return;
+                }
+                if key[d] < high[d] {
+                    key[d] += 1;
+                    break;
+                }
+                key[d] = low[d];
+                d += 1;
+            }
+        }
+    }
+
+    /// Number of cells at a Chebyshev distance of at most `max_offset` cells from the cell `center`.
+    fn box_size(&self, center: &[usize], max_offset: usize) -> f64 {
+        center
+            .iter()
+            .zip(self.shape.iter())
+            .map(|(c, size)| {
+                let high = c.saturating_add(max_offset).min(size - 1);
+                (high - c.saturating_sub(max_offset) + 1) as f64
+            })
+            .product()
+    }
+
+    /// Recomputes the number of available points of each cell from the state of the PointSet.
+    pub(crate) fn sync(&mut self, active: &BitSet, visited: &BitSet) {
+        self.available.iter_mut().for_each(|a| *a = 0);
+        for (i, &cell) in self.cell_of.iter().enumerate() {
+            if active[i] && !visited[i] {
+                self.available[cell] += 1;
+            }
+        }
+        self.live = (0..self.available.len())
+            .filter(|&cell| self.available[cell] > 0)
+            .collect();
+        for (pos, &cell) in self.live.iter().enumerate() {
+            self.live_pos[cell] = pos;
+        }
+    }
+
+    /// Marks a point as not available anymore. Must be called exactly once per point.
+    fn remove(&mut self, idx: usize) {
+        let cell = self.cell_of[idx];
+        self.available[cell] -= 1;
+        if self.available[cell] == 0 {
+            let pos = self.live_pos[cell];
+            self.live.swap_remove(pos);
+            if let Some(&moved) = self.live.get(pos) {
+                self.live_pos[moved] = pos;
+            }
+        }
+    }
+
+    fn members(&self, cell: usize) -> &[usize] {
+        &self.idxs[self.start[cell]..self.start[cell + 1]]
+    }
+
+    /// Returns all points at a distance strictly lower than `radius` from `query`.
+    pub(crate) fn within(
+        &self,
+        points: &[Vec<f64>],
+        metric: Metric,
+        summation: Summation,
+        query: &[f64],
+        radius: f64,
+    ) -> Vec<usize> {
+        let mut result = Vec::new();
+        // The coordinates of two points differ by less than their distance, whatever the metric
+        let reach = ((radius + self.slack) / self.cell_size).ceil() as usize;
+        self.for_each_cell(&self.key(query), 0, reach, |cell, key| {
+            if self.start[cell] == self.start[cell + 1]
+                || self.cell_bound(metric, query, key) >= radius
+            {
+                return;
+            }
+            result.extend(self.members(cell).iter().filter(|&&i| {
+                metric
+                    .distance_below(query, &points[i], radius, summation)
+                    .is_some()
+            }))
+        });
+        result
+    }
+
+    /// Returns the closest available point at a distance of at least `d_min` from `query`.
+    /// `distance` returns the distance of a point to the query if it is lower than the given limit.
+    /// The cells are explored by rings of increasing distance to the cell of the query, until
+    /// the ring holds more cells than the cells with available points: these are then explored directly.
+    pub(crate) fn nearest_available(
+        &self,
+        metric: Metric,
+        query: &[f64],
+        distance: &dyn Fn(usize, f64) -> Option<f64>,
+        is_available: &dyn Fn(usize) -> bool,
+        d_min: f64,
+    ) -> Option<usize> {
+        let center = self.key(query);
+        let mut best: Option<(usize, f64)> = None;
+        let visit = |cell: usize, key: &[usize], best: &mut Option<(usize, f64)>| {
+            if self.available[cell] == 0 {
+                return;
+            }
+            if let Some((_, best_dist)) = best {
+                if self.cell_bound(metric, query, key) >= *best_dist {
+                    return;
+                }
+            }
+            for &i in self.members(cell).iter() {
+                if !is_available(i) {
+                    continue;
+                }
+                let limit = best.map_or(f64::INFINITY, |(_, b)| b);
+                match distance(i, limit) {
+                    Some(dist) if dist >= d_min => *best = Some((i, dist)),
+                    _ => {}
+                }
+            }
+        };
+        for ring in 0.. {
+            if self.live.is_empty() {
+                break;
+            }
+            if self.box_size(&center, ring) >= self.live.len() as f64 {
+                let mut key = center.clone();
+                for &cell in self.live.iter() {
+                    self.decode(cell, &mut key);
+                    if key
+                        .iter()
+                        .zip(center.iter())
+                        .any(|(k, c)| k.abs_diff(*c) >= ring)
+                    {
+                        visit(cell, &key, &mut best);
+                    }
+                }
+                break;
+            }
+            self.for_each_cell(&center, ring, ring, |cell, key| visit(cell, key, &mut best));
+            if let Some((_, best_dist)) = best {
+                if self.ring_bound(metric, ring + 1) >= best_dist {
+                    break;
+                }
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+/// Side of the cells of the grid for the given minimal distance.
+pub(crate) fn cell_size(d_min: f64, d_max: f64) -> f64 {
+    if d_min > 0.0 && d_min.is_finite() {
+        d_min
+    } else if d_max > 0.0 && d_max.is_finite() {
+        d_max
+    } else {
+        1.0
+    }
+}
+
+/// WSP loop of the grid backend. Same semantics as the dense loop: points closer than
+/// the threshold to the origin are removed, then the next origin is the closest valid point.
+pub(crate) fn wsp_loop_grid<F: Fn(usize) -> f64>(
+    set: &mut PointSet,
+    threshold: F,
+    mut origin: usize,
+    stats: &mut RunStats,
+) {
+    let grid = set.grid.as_mut().expect("The grid backend requires a grid");
+    loop {
+        let d_min = threshold(origin);
+        if !set.visited[origin] {
+            set.visited.set(origin, true);
+            stats.nb_origins += 1;
+            if set.active[origin] {
+                grid.remove(origin);
+            }
+        }
+
+        // Remove all points too close to the origin
+        for point_idx in grid.within(
+            &set.points,
+            set.metric,
+            set.summation,
+            &set.points[origin],
+            d_min,
+        ) {
+            stats.nb_iterations += 1;
+            if point_idx != origin && set.active[point_idx] && !set.fixed[point_idx] {
+                set.active.set(point_idx, false);
+                set.nb_active -= 1;
+                stats.nb_removed += 1;
+                if !set.visited[point_idx] {
+                    grid.remove(point_idx);
+                }
+                if let Some(observer) = set.observer.as_mut() {
+                    let distance = set.metric.distance_with(
+                        &set.points[origin],
+                        &set.points[point_idx],
+                        set.summation,
+                    );
+                    observer(origin, point_idx, distance);
+                }
+            }
+        }
+
+        let (points, metric, summation) = (&set.points, set.metric, set.summation);
+        let (active, visited) = (&set.active, &set.visited);
+        match grid.nearest_available(
+            metric,
+            &points[origin],
+            &|i, limit| metric.distance_below(&points[origin], &points[i], limit, summation),
+            &|i| active[i] && !visited[i],
+            d_min,
+        ) {
+            Some(next) => origin = next,
+            None => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{adaptive_wsp, wsp, Backend};
+
+    #[test]
+    fn test_within() {
+        let points = PointSet::init_from_random(500, 3, 51).points;
+        let grid = Grid::new(&points, 0.1);
+        for radius in [0.05, 0.1, 0.3] {
+            let mut found = grid.within(
+                &points,
+                Metric::Euclidean,
+                Summation::Naive,
+                &points[0],
+                radius,
+            );
+            found.sort_unstable();
+            let expected: Vec<usize> = (0..500)
+                .filter(|&i| Metric::Euclidean.distance(&points[0], &points[i]) < radius)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_same_result_as_dense() {
+        for nb_dim in [1, 2, 4] {
+            let points = PointSet::init_from_random(500, nb_dim, 51).points;
+            for d_min in [0.01, 0.2] {
+                let mut dense = PointSet::init_from_preset(points.clone());
+                let mut grid =
+                    PointSet::init_from_preset(points.clone()).with_backend(Backend::Grid);
+                wsp(&mut dense, d_min);
+                wsp(&mut grid, d_min);
+                assert_eq!(dense.active, grid.active);
+                assert_eq!(dense.nb_active, grid.nb_active);
+            }
+
+            // The bounds of the adaptive search are the same as with the other matrix-free backend
+            let mut tree = PointSet::init_from_preset_with_backend(
+                points.clone(),
+                Metric::Euclidean,
+                Backend::KdTree,
+            );
+            let mut grid =
+                PointSet::init_from_preset_with_backend(points, Metric::Euclidean, Backend::Grid);
+            adaptive_wsp(&mut tree, 50, false);
+            adaptive_wsp(&mut grid, 50, false);
+            assert_eq!(tree.active, grid.active);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+//! Persistence of the distance matrix and of the sorted neighbour lists, so that repeated
+//! experiments on the same candidate set skip the O(n² log n) setup.
+
+use crate::binary::{expect_magic, read_f64, read_u64, read_usize, write_f64, write_u64};
+use crate::{
+    Backend, Metric, Normalization, PointSet, Quantization, RngAlgorithm, Summation, WspError,
+};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+const INDEX_MAGIC: &[u8; 8] = b"WSPIDX03";
+
+impl Metric {
+    fn to_code(self) -> u64 {
+        match self {
+            Metric::Manhattan => 0,
+            Metric::Euclidean => 1,
+            Metric::Geodesic => 2,
+        }
+    }
+
+    fn from_code(code: u64) -> Result<Metric, WspError> {
+        match code {
+            0 => Ok(Metric::Manhattan),
+            1 => Ok(Metric::Euclidean),
+            2 => Ok(Metric::Geodesic),
+            _ => Err(WspError::InvalidFile(format!("unknown metric {}", code))),
+        }
+    }
+}
+
+impl Backend {
+    fn to_code(self) -> u64 {
+        match self {
+            Backend::Dense => 0,
+            Backend::KdTree => 1,
+            Backend::Approximate => 2,
+            Backend::Grid => 3,
+        }
+    }
+
+    fn from_code(code: u64) -> Result<Backend, WspError> {
+        match code {
+            0 => Ok(Backend::Dense),
+            1 => Ok(Backend::KdTree),
+            2 => Ok(Backend::Approximate),
+            3 => Ok(Backend::Grid),
+            _ => Err(WspError::InvalidFile(format!("unknown backend {}", code))),
+        }
+    }
+}
+
+impl Summation {
+    fn to_code(self) -> u64 {
+        match self {
+            Summation::Naive => 0,
+            Summation::Neumaier => 1,
+        }
+    }
+
+    fn from_code(code: u64) -> Result<Summation, WspError> {
+        match code {
+            0 => Ok(Summation::Naive),
+            1 => Ok(Summation::Neumaier),
+            _ => Err(WspError::InvalidFile(format!("unknown summation {}", code))),
+        }
+    }
+}
+
+impl RngAlgorithm {
+    fn to_code(self) -> u64 {
+        match self {
+            RngAlgorithm::Small => 0,
+            RngAlgorithm::ChaCha20 => 1,
+            RngAlgorithm::Pcg64 => 2,
+        }
+    }
+
+    fn from_code(code: u64) -> Result<RngAlgorithm, WspError> {
+        match code {
+            0 => Ok(RngAlgorithm::Small),
+            1 => Ok(RngAlgorithm::ChaCha20),
+            2 => Ok(RngAlgorithm::Pcg64),
+            _ => Err(WspError::InvalidFile(format!(
+                "unknown random number generator {}",
+                code
+            ))),
+        }
+    }
+}
+
+fn quantization_to_code(quantization: Option<Quantization>) -> u64 {
+    match quantization {
+        None => 0,
+        Some(Quantization::U16) => 1,
+        Some(Quantization::U8) => 2,
+    }
+}
+
+fn quantization_from_code(code: u64) -> Result<Option<Quantization>, WspError> {
+    match code {
+        0 => Ok(None),
+        1 => Ok(Some(Quantization::U16)),
+        2 => Ok(Some(Quantization::U8)),
+        _ => Err(WspError::InvalidFile(format!(
+            "unknown quantization {}",
+            code
+        ))),
+    }
+}
+
+impl PointSet {
+    /// Stores the points, the distance matrix and the sorted neighbour lists of the PointSet in a binary file.
+    /// The sorted neighbour lists are computed first if needed. The settings of the PointSet (seed and
+    /// generator of the origin, quantization, fixed-point coordinates and normalization) are stored too,
+    /// but not the state of the points (active, visited...).
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path to the file where to store the index.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(100, 10, 51);
+    /// points.save_index("wsp_index.bin").unwrap();
+    ///
+    /// let mut points = wsp::PointSet::load_index("wsp_index.bin").unwrap();
+    /// wsp::wsp(&mut points, 1.0);
+    /// # std::fs::remove_file("wsp_index.bin").unwrap();
+    /// ```
+    pub fn save_index(&mut self, filepath: &str) -> Result<(), Box<dyn Error>> {
+        let mut wrt = BufWriter::new(File::create(filepath)?);
+        self.write_index(&mut wrt)?;
+        wrt.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn write_index<W: Write>(&mut self, wrt: &mut W) -> Result<(), Box<dyn Error>> {
+        if self.backend == Backend::Dense && self.idx_sort.is_empty() {
+            self.compute_closest_idx();
+        }
+
+        wrt.write_all(INDEX_MAGIC)?;
+        write_u64(wrt, self.metric.to_code())?;
+        write_u64(wrt, self.backend.to_code())?;
+        write_u64(wrt, self.summation.to_code())?;
+        write_u64(wrt, self.nb_neighbours.map_or(0, |k| k as u64 + 1))?;
+        write_u64(wrt, self.rng_algorithm.to_code())?;
+        write_u64(wrt, self.origin_seed)?;
+        write_u64(wrt, quantization_to_code(self.quantization()))?;
+        write_u64(wrt, self.fixed_point.map_or(0, |bits| bits as u64 + 1))?;
+        write_f64(wrt, self.d_min)?;
+        write_f64(wrt, self.d_max)?;
+        write_u64(wrt, self.points.len() as u64)?;
+        write_u64(wrt, self.points.first().map_or(0, |p| p.len()) as u64)?;
+        for point in self.points.iter() {
+            for &x in point.iter() {
+                write_f64(wrt, x)?;
+            }
+        }
+        match self.normalization.as_ref() {
+            Some(normalization) => {
+                write_u64(wrt, 1)?;
+                for &x in normalization.lower.iter().chain(normalization.upper.iter()) {
+                    write_f64(wrt, x)?;
+                }
+            }
+            None => write_u64(wrt, 0)?,
+        }
+
+        // The tree of the other backends is fast to rebuild
+        if self.backend == Backend::Dense {
+            // The matrix is symmetric
+            let nb_points = self.points.len();
+            for i in 0..nb_points {
+                for j in i + 1..nb_points {
+                    write_f64(wrt, self.distance(i, j))?;
+                }
+            }
+            for idxs in self.idx_sort.iter() {
+                write_u64(wrt, idxs.len() as u64)?;
+                for &j in idxs.iter() {
+                    write_u64(wrt, j as u64)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a 'PointSet' from a file written by [`PointSet::save_index`],
+    /// without recomputing the distances nor sorting the neighbours.
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path to the index file.
+    pub fn load_index(filepath: &str) -> Result<PointSet, Box<dyn Error>> {
+        let mut rdr = BufReader::new(File::open(filepath)?);
+        PointSet::read_index(&mut rdr)
+    }
+
+    pub(crate) fn read_index<R: Read>(rdr: &mut R) -> Result<PointSet, Box<dyn Error>> {
+        expect_magic(rdr, INDEX_MAGIC)?;
+        let metric = Metric::from_code(read_u64(rdr)?)?;
+        let backend = Backend::from_code(read_u64(rdr)?)?;
+        let summation = Summation::from_code(read_u64(rdr)?)?;
+        let nb_neighbours = match read_usize(rdr)? {
+            0 => None,
+            k => Some(k - 1),
+        };
+        let rng_algorithm = RngAlgorithm::from_code(read_u64(rdr)?)?;
+        let origin_seed = read_u64(rdr)?;
+        let quantization = quantization_from_code(read_u64(rdr)?)?;
+        let fixed_point = match read_u64(rdr)? {
+            0 => None,
+            bits => Some(u32::try_from(bits - 1).map_err(|_| {
+                WspError::InvalidFile(format!("invalid number of fixed-point bits {}", bits - 1))
+            })?),
+        };
+        let d_min = read_f64(rdr)?;
+        let d_max = read_f64(rdr)?;
+        let nb_points = read_usize(rdr)?;
+        let nb_dim = read_usize(rdr)?;
+        let points = (0..nb_points)
+            .map(|_| (0..nb_dim).map(|_| read_f64(rdr)).collect())
+            .collect::<Result<Vec<Vec<f64>>, _>>()?;
+        let normalization = match read_u64(rdr)? {
+            0 => None,
+            _ => {
+                let mut bounds = (0..2 * nb_dim)
+                    .map(|_| read_f64(rdr))
+                    .collect::<Result<Vec<f64>, _>>()?;
+                let upper = bounds.split_off(nb_dim);
+                Some(Normalization {
+                    lower: bounds,
+                    upper,
+                })
+            }
+        };
+
+        let mut p = if backend == Backend::Dense {
+            PointSet::read_dense_index(rdr, points, d_min, d_max, metric)?
+        } else {
+            PointSet::init_with_summation(points, metric, backend, summation, None)
+        };
+        p.nb_neighbours = nb_neighbours;
+        p.summation = summation;
+        p.rng_algorithm = rng_algorithm;
+        p.origin_seed = origin_seed;
+        p.fixed_point = fixed_point;
+        p.normalization = normalization;
+        Ok(match quantization {
+            Some(quantization) => p.with_quantization(quantization),
+            None => p,
+        })
+    }
+
+    /// Reads the distance matrix and the sorted neighbour lists of the dense backend.
+    fn read_dense_index<R: Read>(
+        rdr: &mut R,
+        points: Vec<Vec<f64>>,
+        d_min: f64,
+        d_max: f64,
+        metric: Metric,
+    ) -> Result<PointSet, Box<dyn Error>> {
+        let nb_points = points.len();
+
+        // Only the upper triangle of the symmetric matrix is stored
+        let upper = (0..nb_points * nb_points.saturating_sub(1) / 2)
+            .map(|_| read_f64(rdr))
+            .collect::<Result<Vec<f64>, _>>()?;
+        let upper_idx = |i: usize, j: usize| i * nb_points - i * (i + 1) / 2 + j - i - 1;
+        let distance_matrix: Vec<Vec<f64>> = (0..nb_points)
+            .map(|i| {
+                (0..nb_points)
+                    .map(|j| match i.cmp(&j) {
+                        Ordering::Less => upper[upper_idx(i, j)],
+                        Ordering::Greater => upper[upper_idx(j, i)],
+                        Ordering::Equal => 0.0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut idx_sort: Vec<Vec<usize>> = Vec::with_capacity(nb_points);
+        for _ in 0..nb_points {
+            let len = read_usize(rdr)?;
+            let idxs = (0..len)
+                .map(|_| read_usize(rdr))
+                .collect::<Result<Vec<usize>, _>>()?;
+            if idxs.iter().any(|&j| j >= nb_points) {
+                return Err(Box::new(WspError::InvalidFile(
+                    "neighbour index out of bounds".to_string(),
+                )));
+            }
+            idx_sort.push(idxs);
+        }
+
+        let mut p =
+            PointSet::init_from_distance_matrix(points, distance_matrix, d_min, d_max, metric);
+        p.idx_sort = idx_sort;
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsp;
+
+    #[test]
+    fn test_index_round_trip() {
+        let filepath = std::env::temp_dir().join("wsp_test_index_round_trip.bin");
+        let filepath = filepath.to_str().unwrap();
+
+        let mut original = PointSet::init_from_random(300, 4, 51).with_nb_neighbours(20);
+        original.save_index(filepath).unwrap();
+        let mut loaded = PointSet::load_index(filepath).unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(loaded.points, original.points);
+        assert_eq!(loaded.distance_matrix, original.distance_matrix);
+        assert_eq!(loaded.idx_sort, original.idx_sort);
+        assert_eq!(loaded.nb_neighbours, Some(20));
+
+        wsp(&mut original, 0.3);
+        wsp(&mut loaded, 0.3);
+        assert_eq!(loaded.active, original.active);
+    }
+
+    #[test]
+    fn test_index_keeps_settings() {
+        let filepath = std::env::temp_dir().join("wsp_test_index_settings.bin");
+        let filepath = filepath.to_str().unwrap();
+
+        let points = PointSet::init_from_random(200, 3, 51).points;
+        let mut original = PointSet::init_from_preset_normalized(points)
+            .unwrap()
+            .with_seed(12345)
+            .with_rng_algorithm(RngAlgorithm::ChaCha20)
+            .with_fixed_point(20)
+            .with_quantization(Quantization::U16);
+        original.save_index(filepath).unwrap();
+        let mut loaded = PointSet::load_index(filepath).unwrap();
+        std::fs::remove_file(filepath).unwrap();
+
+        assert_eq!(loaded.rng_algorithm(), RngAlgorithm::ChaCha20);
+        assert_eq!(loaded.origin_seed, original.origin_seed);
+        assert_eq!(loaded.fixed_point(), Some(20));
+        assert_eq!(loaded.quantization(), Some(Quantization::U16));
+        assert_eq!(loaded.normalization(), original.normalization());
+
+        wsp(&mut original, 0.3);
+        wsp(&mut loaded, 0.3);
+        assert_eq!(loaded.active, original.active);
+    }
+
+    #[test]
+    fn test_index_invalid_file() {
+        let mut rdr: &[u8] = b"NOTANINDEX";
+        assert!(PointSet::read_index(&mut rdr).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! Matrix-free backend of the WSP algorithm, based on a KD-tree.
+//! Each node of the tree tracks its number of "available" points, i.e., points that are
+//! still active and have not been visited yet, so that the search of the next origin
+//! skips the parts of the space that have already been cleared.
+
+use crate::{BitSet, Metric, PointSet, RemovalObserver, RunStats, Summation};
+
+/// Maximal number of points in a leaf of the tree.
+const LEAF_SIZE: usize = 16;
+/// Approximation factor of the next origin search for the approximate backend:
+/// the next origin is at most (1 + APPROX_EPSILON) times farther than the closest valid point.
+pub(crate) const APPROX_EPSILON: f64 = 1.0;
+
+struct Node {
+    /// Range of the node in `KdTree::idxs`
+    start: usize,
+    end: usize,
+    /// Splitting dimension and value, and children, for internal nodes
+    split: Option<(usize, f64, usize, usize)>,
+    parent: Option<usize>,
+}
+
+/// Storage of the coordinates indexed by the tree: one vector per point, or a borrowed row-major slice.
+pub(crate) trait Coordinates {
+    fn nb_points(&self) -> usize;
+    fn point(&self, i: usize) -> &[f64];
+}
+
+impl Coordinates for [Vec<f64>] {
+    fn nb_points(&self) -> usize {
+        self.len()
+    }
+
+    fn point(&self, i: usize) -> &[f64] {
+        &self[i]
+    }
+}
+
+impl Coordinates for Vec<Vec<f64>> {
+    fn nb_points(&self) -> usize {
+        self.len()
+    }
+
+    fn point(&self, i: usize) -> &[f64] {
+        &self[i]
+    }
+}
+
+pub(crate) struct KdTree {
+    nodes: Vec<Node>,
+    /// Permutation of the point indexes. Each node covers a contiguous range
+    idxs: Vec<usize>,
+    /// Leaf containing each point
+    leaf_of: Vec<usize>,
+    /// Number of available points in each node
+    available: Vec<usize>,
+}
+
+impl KdTree {
+    pub(crate) fn new<P: Coordinates + ?Sized>(points: &P) -> KdTree {
+        let nb_points = points.nb_points();
+        let mut tree = KdTree {
+            nodes: Vec::new(),
+            idxs: (0..nb_points).collect(),
+            leaf_of: vec![0; nb_points],
+            available: Vec::new(),
+        };
+        if nb_points > 0 {
+            tree.build(points, 0, nb_points, None);
+        }
+        tree.available = tree.nodes.iter().map(|n| n.end - n.start).collect();
+        tree
+    }
+
+    fn build<P: Coordinates + ?Sized>(
+        &mut self,
+        points: &P,
+        start: usize,
+        end: usize,
+        parent: Option<usize>,
+    ) -> usize {
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            start,
+            end,
+            split: None,
+            parent,
+        });
+
+        // Split along the dimension with the largest spread
+        let nb_dim = points.point(self.idxs[start]).len();
+        let (split_dim, spread) = (0..nb_dim)
+            .map(|d| {
+                let (min, max) =
+                    self.idxs[start..end]
+                        .iter()
+                        .fold((f64::MAX, f64::MIN), |(min, max), &i| {
+                            let x = points.point(i)[d];
+                            (min.min(x), max.max(x))
+                        });
+                (d, max - min)
+            })
+            .fold(
+                (0, 0.0),
+                |best, cur| if cur.1 > best.1 { cur } else { best },
+            );
+
+        if end - start <= LEAF_SIZE || spread <= 0.0 {
+            self.idxs[start..end]
+                .iter()
+                .for_each(|&i| self.leaf_of[i] = node);
+            return node;
+        }
+
+        let mid = start + (end - start) / 2;
+        self.idxs[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            points.point(a)[split_dim]
+                .partial_cmp(&points.point(b)[split_dim])
+                .unwrap()
+        });
+        let split_val = points.point(self.idxs[mid])[split_dim];
+        let left = self.build(points, start, mid, Some(node));
+        let right = self.build(points, mid, end, Some(node));
+        self.nodes[node].split = Some((split_dim, split_val, left, right));
+        node
+    }
+
+    /// Recomputes the number of available points of each node from the state of the PointSet.
+    pub(crate) fn sync(&mut self, active: &BitSet, visited: &BitSet) {
+        for node in (0..self.nodes.len()).rev() {
+            self.available[node] = match self.nodes[node].split {
+                Some((_, _, left, right)) => self.available[left] + self.available[right],
+                None => self.idxs[self.nodes[node].start..self.nodes[node].end]
+                    .iter()
+                    .filter(|&&i| active[i] && !visited[i])
+                    .count(),
+            };
+        }
+    }
+
+    /// Marks a point as not available anymore. Must be called exactly once per point.
+    pub(crate) fn remove(&mut self, idx: usize) {
+        let mut node = Some(self.leaf_of[idx]);
+        while let Some(n) = node {
+            self.available[n] -= 1;
+            node = self.nodes[n].parent;
+        }
+    }
+
+    /// Returns all points at a distance strictly lower than `radius` from `query`.
+    pub(crate) fn within<P: Coordinates + ?Sized>(
+        &self,
+        points: &P,
+        metric: Metric,
+        summation: Summation,
+        query: &[f64],
+        radius: f64,
+    ) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            match self.nodes[node].split {
+                Some((dim, val, left, right)) => {
+                    let (near, far) = if query[dim] < val {
+                        (left, right)
+                    } else {
+                        (right, left)
+                    };
+                    stack.push(near);
+                    if metric.axis_lower_bound((query[dim] - val).abs()) < radius {
+                        stack.push(far);
+                    }
+                }
+                None => result.extend(
+                    self.idxs[self.nodes[node].start..self.nodes[node].end]
+                        .iter()
+                        .filter(|&&i| {
+                            metric
+                                .distance_below(query, points.point(i), radius, summation)
+                                .is_some()
+                        }),
+                ),
+            }
+        }
+        result
+    }
+
+    /// Returns the closest available point at a distance of at least `d_min` from `query`.
+    /// `distance` returns the distance of a point to the query if it is lower than the given limit.
+    /// With a positive `epsilon`, the result is at most (1 + `epsilon`) times farther than the closest one.
+    pub(crate) fn nearest_available(
+        &self,
+        metric: Metric,
+        query: &[f64],
+        distance: &dyn Fn(usize, f64) -> Option<f64>,
+        is_available: &dyn Fn(usize) -> bool,
+        d_min: f64,
+        epsilon: f64,
+    ) -> Option<usize> {
+        let mut best: Option<(usize, f64)> = None;
+        // Nodes to explore, with a lower bound of their distance to the query
+        let mut stack = vec![(0, 0.0)];
+        while let Some((node, bound)) = stack.pop() {
+            if self.available[node] == 0 {
+                continue;
+            }
+            if let Some((_, best_dist)) = best {
+                if bound * (1.0 + epsilon) >= best_dist {
+                    continue;
+                }
+            }
+            match self.nodes[node].split {
+                Some((dim, val, left, right)) => {
+                    let (near, far) = if query[dim] < val {
+                        (left, right)
+                    } else {
+                        (right, left)
+                    };
+                    let far_bound = bound.max(metric.axis_lower_bound((query[dim] - val).abs()));
+                    // Explore the near side first
+                    stack.push((far, far_bound));
+                    stack.push((near, bound));
+                }
+                None => {
+                    for &i in self.idxs[self.nodes[node].start..self.nodes[node].end].iter() {
+                        if !is_available(i) {
+                            continue;
+                        }
+                        let limit = best.map_or(f64::INFINITY, |(_, b)| b);
+                        match distance(i, limit) {
+                            Some(dist) if dist >= d_min => best = Some((i, dist)),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+/// State of the points updated by the WSP loop of the tree-based backends, borrowed from the set
+/// being thinned: a [`PointSet`] or a [`crate::BorrowedPointSet`].
+pub(crate) struct TreeWalk<'a, P: Coordinates + ?Sized> {
+    pub(crate) points: &'a P,
+    pub(crate) metric: Metric,
+    pub(crate) summation: Summation,
+    pub(crate) tree: &'a mut KdTree,
+    pub(crate) active: &'a mut BitSet,
+    pub(crate) nb_active: &'a mut usize,
+    pub(crate) visited: &'a mut BitSet,
+    pub(crate) fixed: &'a [bool],
+    pub(crate) observer: Option<&'a mut RemovalObserver>,
+}
+
+impl<P: Coordinates + ?Sized> TreeWalk<'_, P> {
+    /// WSP loop of the tree-based backends. Same semantics as the dense loop: points closer than
+    /// the threshold to the origin are removed, then the next origin is the closest valid point.
+    pub(crate) fn run<F: Fn(usize) -> f64>(
+        &mut self,
+        threshold: F,
+        mut origin: usize,
+        epsilon: f64,
+        stats: &mut RunStats,
+    ) {
+        let (points, metric, summation) = (self.points, self.metric, self.summation);
+        loop {
+            let d_min = threshold(origin);
+            let query = points.point(origin);
+            if !self.visited[origin] {
+                self.visited.set(origin, true);
+                stats.nb_origins += 1;
+                if self.active[origin] {
+                    self.tree.remove(origin);
+                }
+            }
+
+            // Remove all points too close to the origin
+            for point_idx in self.tree.within(points, metric, summation, query, d_min) {
+                stats.nb_iterations += 1;
+                if point_idx != origin && self.active[point_idx] && !self.fixed[point_idx] {
+                    self.active.set(point_idx, false);
+                    *self.nb_active -= 1;
+                    stats.nb_removed += 1;
+                    if !self.visited[point_idx] {
+                        self.tree.remove(point_idx);
+                    }
+                    if let Some(observer) = self.observer.as_mut() {
+                        let distance =
+                            metric.distance_with(query, points.point(point_idx), summation);
+                        observer(origin, point_idx, distance);
+                    }
+                }
+            }
+
+            let (active, visited) = (&*self.active, &*self.visited);
+            match self.tree.nearest_available(
+                metric,
+                query,
+                &|i, limit| metric.distance_below(query, points.point(i), limit, summation),
+                &|i| active[i] && !visited[i],
+                d_min,
+                epsilon,
+            ) {
+                Some(next) => origin = next,
+                None => return,
+            }
+        }
+    }
+}
+
+/// WSP loop of the tree-based backends for a PointSet, see [`TreeWalk::run`].
+pub(crate) fn wsp_loop_tree<F: Fn(usize) -> f64>(
+    set: &mut PointSet,
+    threshold: F,
+    origin: usize,
+    epsilon: f64,
+    stats: &mut RunStats,
+) {
+    let mut walk = TreeWalk {
+        points: &set.points,
+        metric: set.metric,
+        summation: set.summation,
+        tree: set
+            .kdtree
+            .as_mut()
+            .expect("Tree-based backends require a KD-tree"),
+        active: &mut set.active,
+        nb_active: &mut set.nb_active,
+        visited: &mut set.visited,
+        fixed: &set.fixed,
+        observer: set.observer.as_deref_mut(),
+    };
+    walk.run(threshold, origin, epsilon, stats);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wsp, Backend};
+
+    #[test]
+    fn test_within() {
+        let points = PointSet::init_from_random(500, 3, 51).points;
+        let tree = KdTree::new(&points);
+        let mut found = tree.within(
+            &points,
+            Metric::Manhattan,
+            Summation::Naive,
+            &points[0],
+            0.3,
+        );
+        found.sort_unstable();
+        let expected: Vec<usize> = (0..500)
+            .filter(|&i| Metric::Manhattan.distance(&points[0], &points[i]) < 0.3)
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_same_result_as_dense() {
+        let points = PointSet::init_from_random(500, 3, 51).points;
+        let mut dense = PointSet::init_from_preset(points.clone());
+        let mut tree = PointSet::init_from_preset(points).with_backend(Backend::KdTree);
+        wsp(&mut dense, 0.2);
+        wsp(&mut tree, 0.2);
+        assert_eq!(dense.active, tree.active);
+        assert_eq!(dense.nb_active, tree.nb_active);
+    }
+
+    #[test]
+    fn test_approximate_min_dist_ok() {
+        let d_min = 0.2;
+        let mut points = PointSet::init_from_random(500, 3, 51).with_backend(Backend::Approximate);
+        wsp(&mut points, d_min);
+        for i in 0..500 {
+            for j in i + 1..500 {
+                if points.active[i] && points.active[j] {
+                    assert!(points.distance(i, j) >= d_min);
+                }
+            }
+        }
+    }
+}
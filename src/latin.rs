@@ -0,0 +1,118 @@
+//! Latinization of a design. WSP spreads the points in the whole space, but does not control their
+//! projections on each dimension, which matter when only a few dimensions turn out to be influential.
+//! Latinizing moves the points so that each 1-D projection is evenly stratified, as in a Latin hypercube.
+
+use crate::{DMin, PointSet};
+
+/// Returns the active points of the PointSet, moved so that the projection of the design on each dimension
+/// is evenly stratified. With `k` active points, the range of the candidates on each dimension is split
+/// in `k` strata of equal width, and the point of rank `r` along the dimension is moved to the middle
+/// of stratum `r`. The dimensions are processed one after the other, and the points in increasing rank.
+///
+/// A move is shortened (down to no move at all) when it would bring the point closer than `d_min` to
+/// another point of the design, unless that pair was already closer. The stratification is then only
+/// approximate. The points are returned in increasing order of index, in the coordinates of the PointSet.
+/// The moved points may leave a constrained domain, e.g. the simplex or the hypersphere.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance, after the algorithm.
+/// * `d_min` - The minimal distance to preserve between the points. See [`crate::wsp`].
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+/// wsp::wsp(&mut points, 0.1);
+/// let design = wsp::latinize(&points, 0.1);
+/// assert_eq!(design.len(), points.nb_active);
+/// ```
+pub fn latinize(set: &PointSet, d_min: impl Into<DMin>) -> Vec<Vec<f64>> {
+    let d_min = set.resolve_d_min(d_min.into());
+    let mut design = set.get_remaining();
+    let nb_points = design.len();
+    let nb_dim = design.first().map_or(0, |p| p.len());
+    if nb_points < 2 {
+        return design;
+    }
+
+    for dim in 0..nb_dim {
+        let (lower, upper) = set
+            .points
+            .iter()
+            .map(|p| p[dim])
+            .filter(|x| !x.is_nan())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, u), x| {
+                (l.min(x), u.max(x))
+            });
+        let width = (upper - lower) / nb_points as f64;
+        let mut ranks: Vec<usize> = (0..nb_points).collect();
+        ranks.sort_by(|&a, &b| design[a][dim].total_cmp(&design[b][dim]));
+
+        for (rank, &i) in ranks.iter().enumerate() {
+            let current = design[i][dim];
+            let target = lower + (rank as f64 + 0.5) * width;
+            // Shorten the move by halves until it keeps the distances
+            for fraction in [1.0, 0.5, 0.25, 0.125] {
+                let mut moved = design[i].clone();
+                moved[dim] = current + fraction * (target - current);
+                if keeps_distances(set, &design, i, &moved, d_min) {
+                    design[i] = moved;
+                    break;
+                }
+            }
+        }
+    }
+    design
+}
+
+/// Whether moving point `i` of the design to `moved` brings it closer than `d_min` to no other point,
+/// except the points that were already closer.
+fn keeps_distances(
+    set: &PointSet,
+    design: &[Vec<f64>],
+    i: usize,
+    moved: &[f64],
+    d_min: f64,
+) -> bool {
+    let distance = |p: &[f64], q: &[f64]| set.metric().distance_with(p, q, set.summation());
+    design.iter().enumerate().all(|(j, other)| {
+        j == i || distance(moved, other) >= d_min.min(distance(&design[i], other))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{min_distance, wsp};
+
+    #[test]
+    fn test_latinize() {
+        // Without constraint, each projection is exactly stratified
+        let mut points = PointSet::init_from_random(1000, 3, 51);
+        wsp(&mut points, 0.3);
+        let k = points.nb_active;
+        let design = latinize(&points, 0.0);
+        let nb_strata = |design: &[Vec<f64>], dim: usize| {
+            let lower = points.points.iter().map(|p| p[dim]).fold(1.0, f64::min);
+            let upper = points.points.iter().map(|p| p[dim]).fold(0.0, f64::max);
+            let mut strata: Vec<usize> = design
+                .iter()
+                .map(|p| ((p[dim] - lower) / (upper - lower) * k as f64).floor() as usize)
+                .collect();
+            strata.sort_unstable();
+            strata.dedup();
+            strata.len()
+        };
+        assert!((0..3).all(|dim| nb_strata(&design, dim) == k));
+
+        // With a minimal distance, the design stays as separated as WSP made it
+        let separation = min_distance(&points);
+        let design = latinize(&points, separation);
+        assert_eq!(design.len(), k);
+        let initial = points.get_remaining();
+        assert!((0..3).all(|dim| nb_strata(&design, dim) > nb_strata(&initial, dim)));
+        let latinized = PointSet::init_from_preset(design);
+        assert!(min_distance(&latinized) >= separation);
+    }
+}
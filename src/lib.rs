@@ -59,6 +59,36 @@
 //! }
 //! ```
 //!
+//! #### Exploring designs
+//!
+//! A snapshot captures which points are active, visited and fixed, with one bit per flag. Restoring it rolls the set back without recomputing the distances, e.g. to try several minimal distances:
+//!
+//! ```rust
+//! let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+//! let initial = points.snapshot();
+//! for d_min in [2.5, 3.0, 3.5] {
+//!     points.restore(&initial);
+//!     wsp::wsp(&mut points, d_min);
+//!     println!("d_min = {}: {} points", d_min, points.nb_active);
+//! }
+//! ```
+//!
+//! #### Surrogate models
+//!
+//! The design can be handed to a surrogate-model trainer (Gaussian process, kriging...) without intermediate files, e.g. as the sampling stage of a Bayesian optimization loop. Any `wsp::DesignConsumer`, including a closure, receives the coordinates of the selected points and their indices among the candidates; `wsp::RowMajor` passes them as a contiguous row-major matrix instead:
+//!
+//! ```rust
+//! let mut points = wsp::PointSet::init_from_random(1000, 3, 51);
+//! wsp::wsp(&mut points, 0.3);
+//! let nb_samples = points.feed_design(
+//!     wsp::OutputScale::Original,
+//!     &mut wsp::RowMajor(|x: &[f64], nb_points, nb_dim, indices: &[usize]| {
+//!         // e.g. ndarray::Array2::from_shape_vec((nb_points, nb_dim), x.to_vec())
+//!         nb_points
+//!     }),
+//! );
+//! ```
+//!
 //! ## Binary usage
 
 //! Use `cargo install wsp` to install a binary version of the rust-wsp crate. Both `wsp()` and `adaptive_wsp` are available through the command line.
@@ -73,6 +103,12 @@
 //!
 //! This will run the WSP algorithm with 1000 initial points. Each point has a dimension of 10. The minimal distance between each point, as detailed in the paper, is set to 0.5. For now, the algorithm uses the l1 (Manhattan) distance, as it provides better separation in high dimensional space than the l2 (euclidian) distance. The result is stored in a file named `wsp.csv`. Each row represents a point in a 20 dimensions space that is far enough from its other neighbours. You may change the output file with the `-o` option.
 //!
+//! Since absolute distances are hard to choose before inspecting the data, the minimal distance may also be given as a fraction of the maximal (`--relative max`) or mean (`--relative mean`) distance between the initial points:
+//!
+//! ```bash
+//! $ wsp -n 1000 -m 20 -d 0.15 --relative max
+//! ```
+//!
 //! ### Adaptive WSP
 //!
 //! ```bash
@@ -110,48 +146,408 @@
 //!
 //! The algorithm performs 54 iterations until the minimal distance search space is completely explored. It will recompute the space (if needed) qith the minimal distance resulting in the best approximation of the target number of active points in the set. Here, it is 201, with an error of 1 compared to the objective. The resulting matrix is also stored in a file named `wsp.csv` by default.
 //!
+//! ### Mixture designs
+//!
+//! Use `-a simplex` to generate the initial points uniformly on the unit simplex (all coordinates are non-negative and sum to 1), as commonly required by formulation experiments:
+//!
+//! ```bash
+//! $ wsp -n 1000 -m 3 -d 0.1 -a simplex
+//! ```
+//!
+//! Similarly, `-a sphere` generates the initial points uniformly on the surface of the unit hypersphere, e.g., to thin direction vectors. In this case, the distance between two points is the geodesic distance (the angle between them, in radians).
+//!
+//! ### Sensitivity to the minimal distance
+//!
+//! The design is a step function of the minimal distance: a small change of the distance may change many points. Before committing to expensive experiments, `--sensitivity <delta>` runs the algorithm with the distances `d - delta` and `d + delta` as well, and reports how many points enter and leave the design. The `wsp::sensitivity` function gives the same report in the crate:
+//!
+//! ```bash
+//! $ wsp -n 2000 -m 5 -d 0.5 --sensitivity 0.02
+//! d_min = 0.5: 439 points
+//! d_min = 0.48: 502 points (358 entering, 295 leaving)
+//! d_min = 0.52: 405 points (286 entering, 320 leaving)
+//! Largest change: 148.7%
+//! ```
+//!
+//! ### Annealing
+//!
+//! On strongly clustered candidate sets, a single pass lingers in the dense clusters. With `--anneal <nb-passes>`, the algorithm first selects a coarse design with a large minimal distance, then refines it over the following passes, dividing the minimal distance by 2 at each pass (see `--cooling`) down to the requested one. The final design contains all the coarse designs:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 5 -d 0.8 --anneal 3
+//! ```
+//!
+//! ### Best of several runs
+//!
+//! The design depends on the first origin, drawn at random. With `--restarts <nb-restarts>`, the classic algorithm runs from several origins and keeps the best design. `--select` defines the best design: `max-active` (most points, the default), `max-min-distance` (largest minimal distance between two points) or `min-phi:<p>` (smallest Morris-Mitchell criterion, e.g. `min-phi:50`). With `--adaptive`, `--select` chooses between the iterations equally close to the target number of points:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 5 -d 0.8 --restarts 10 --select max-min-distance
+//! ```
+//!
+//! In the crate, `wsp::wsp_restarts` and `wsp::wsp_seeds` (one run per seed) take a `wsp::SelectionCriterion`, as does `wsp::adaptive_wsp_with_selection`.
+//!
+//! ### Latinized designs
+//!
+//! WSP spreads the points in the whole space, but does not control their projections on each dimension. With `--latinize`, the points of the design are moved so that each projection is evenly stratified, as in a Latin hypercube. A move is shortened when it would decrease the minimal distance between the points of the design, so the stratification may only be approximate. The same post-processing is available in the crate with `wsp::latinize`:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 5 -d 0.8 --latinize
+//! ```
+//!
+//! ### Cluster-aware WSP
+//!
+//! When the candidate set is multimodal, a single global minimal distance may erase the smallest modes. With `--clusters k`, the candidates are first partitioned with k-means, and the adaptive algorithm runs in each cluster with a target number of points proportional to the size of the cluster:
+//!
+//! ```bash
+//! $ wsp -n 1000 -m 20 --adaptive 100 --clusters 4
+//! ```
+//!
+//! ### Backends
+//!
+//! By default, the backend is chosen from the size of the problem: the full distance matrix is stored for small candidate sets (`dense`), a KD-tree is used without storing any distance for large sets (`kdtree`), and the next origin is only approximately the closest valid point for huge sets (`approximate`). In low dimensions (up to 6), large sets use instead a uniform grid whose cells have the size of the minimal distance (`grid`): the neighbours of an origin are found by scanning the adjacent cells only. The minimal distance between the remaining points is guaranteed with all backends. You may override this choice with the `-b` option:
+//!
+//! ```bash
+//! $ wsp -n 200000 -m 3 -d 0.02 -b kdtree
+//! ```
+//!
+//! ### Accurate distances
+//!
+//! In high dimensions, or with coordinates of very different magnitudes, the rounding errors of the summation of the per-dimension terms can decide whether a pair of points is closer than the minimal distance. The `--summation neumaier` option uses a compensated summation, whose error does not depend on the dimension, at the cost of a slower computation of the distances:
+//!
+//! ```bash
+//! $ wsp -n 1000 -m 500 -d 9.0 --summation neumaier
+//! ```
+//!
+//! ### Identical designs on all platforms
+//!
+//! A distance computed a few ulps apart on another architecture or with another compiler can flip its comparison with the minimal distance, and change the design. The `--fixed-point <bits>` option snaps the coordinates to multiples of `2^-bits` before the algorithm, which makes the l1 and l2 distances exact: the same candidates then give the same design on x86, ARM and WASM, provided the origins are drawn with a portable generator (`--rng chacha20` or `--rng pcg64`, see below). The design is made of the snapped candidates. With 20 bits, the l2 distances stay exact up to 8192 dimensions in the unit cube. The geodesic distance is not covered. In the crate, use `PointSet::with_fixed_point`:
+//!
+//! ```bash
+//! $ wsp -n 1000 -m 20 -d 3.0 --fixed-point 20 --rng chacha20
+//! ```
+//!
+//! ### Memory usage
+//!
+//! The dense backend stores the distance between all pairs of points, and the list of all the other points sorted by distance for each point: 8 bytes per pair each. With `--quantize u16` (or `u8`), each distance is stored with 2 bytes (or 1 byte) instead, coded as soon as it is computed, and the sorted lists are truncated to their 64 closest neighbours (extended when needed): 20000 points take 0.8 GB (or 0.4 GB) instead of 6.4 GB. The result does not change: the distances too close to the minimal distance to be decided from their code are recomputed exactly:
+//!
+//! ```bash
+//! $ wsp -n 20000 -m 50 -d 9.0 -b dense --quantize u16
+//! ```
+//!
+//! ### Reusing the index
+//!
+//! Computing the distances and sorting the neighbours of each point dominates the setup time. Store them once with `--save-index`, and reuse them for other distances or targets with `--load-index`:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 20 -d 3.0 --save-index index.bin
+//! $ wsp --load-index index.bin --adaptive 100
+//! ```
+//!
+//! ### Resuming an adaptive run
+//!
+//! With `--checkpoint`, the adaptive algorithm stores its state after each iteration (together with the index). If the run is interrupted, continue it with `--resume`. The resumed run keeps the seed and the generator of the original run, so `--seed` and `--rng` are rejected:
+//!
+//! ```bash
+//! $ wsp -n 20000 -m 20 --adaptive 500 --checkpoint state.bin
+//! $ wsp --resume state.bin
+//! ```
+//!
+//! ### Binary input and output
+//!
+//! For large candidate sets, parsing CSV can take longer than the algorithm itself. Points can instead be stored in a raw binary format: the number of rows and columns as two little-endian `u64`, followed by the coordinates as row-major little-endian `f64`. Read candidates from such a file with `--input` (other files are streamed as CSV, one point per row); outputs whose name ends with `.bin` use the same format:
+//!
+//! ```bash
+//! $ wsp --input candidates.bin -d 0.5 -o wsp.bin
+//! ```
+//!
+//! ### Huge candidate pools
+//!
+//! The cost of WSP grows quadratically with the number of candidates. A huge input pool may first be reduced with `--subsample`: `uniform:<nb-points>` keeps random candidates, and `grid:<cell-size>` keeps one candidate per cell of a regular grid, thinning the dense regions of the pool. WSP then runs exactly on the survivors, which are the candidates of the exported files:
+//!
+//! ```bash
+//! $ wsp --input pool.csv --subsample uniform:20000 -d 0.1
+//! ```
+//!
+//! ### Streaming candidates
+//!
+//! With `--stream`, the pool is never stored: the candidates are generated (or read from a CSV `--input`) one at a time, and each candidate is kept if it is at least the minimal distance away from the points already kept, found with a hash grid. The memory only depends on the size of the design, so the number of candidates is unbounded. The design depends on the order of the candidates instead of the chain of closest origins of WSP. In the crate, `wsp::StreamingWsp` accepts candidates from any source:
+//!
+//! ```bash
+//! $ wsp --stream -n 100000000 -m 3 -d 0.05
+//! ```
+//!
+//! ### Physical units
+//!
+//! When the candidate points are expressed in physical units, `--normalize` maps each dimension onto [0, 1] before running the algorithm, so that all dimensions weigh the same (the distance is then expressed in normalized units). The output is written in the original units by default, or on the unit cube with `--output-scale normalized`. In both cases, the bounds of each dimension are stored next to the output in a `.transform.csv` file:
+//!
+//! ```bash
+//! $ wsp --input experiments.csv --normalize -d 0.2 -o design.csv --output-scale normalized
+//! $ cat design.transform.csv
+//! dim,lower,upper
+//! 0,20.0,80.0
+//! 1,0.5,3.0
+//! ```
+//!
+//! ### Experimental ranges
+//!
+//! The design is computed on the unit cube. Map it onto the real ranges of each factor at export time with `--bounds`, given as `<lower>:<upper>` for each dimension (use `--bounds=` if the first bound is negative):
+//!
+//! ```bash
+//! $ wsp -m 3 -d 0.3 --bounds=-5:5,0:10,100:200
+//! ```
+//!
+//! ### Representatives of the removed candidates
+//!
+//! To aggregate observations of the full candidate set onto the design, store with `--representatives` the closest remaining point of each removed candidate. Each row of the CSV file gives the index of the candidate, the index of its representative, and the row of the representative in the output file:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 20 -d 3.0 --representatives representatives.csv
+//! ```
+//!
+//! ### Exporting a run
+//!
+//! With `--export <prefix>`, a single run stores a coherent set of CSV files sharing the prefix, instead of the `-i`/`-o` pair: the initial candidates (`<prefix>.initial.csv`), the design (`<prefix>.design.csv`), the active mask (`<prefix>.mask.csv`, one `candidate,active` row per candidate), the representatives of the removed candidates (`<prefix>.representatives.csv`) and the manifest (`<prefix>.manifest`). Candidates are indexed by their row in the initial file, and design points by their row in the design file:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 20 -d 3.0 --export run
+//! ```
+//!
+//! ### Run statistics
+//!
+//! Store statistics about the execution (removed points, origins, loop iterations, setup and loop times in seconds) in a CSV file with `--stats`, e.g. to log them in an experiment tracker:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 20 -d 3.0 --stats stats.csv
+//! ```
+//!
+//! To diagnose a slow run, `--verbose` also prints the time spent in each phase of the work done on the candidates: computation of the distance matrix, sorting of the neighbours, building of the spatial index and selection loop, with their counters. In the crate, they are returned by `PointSet::stats`.
+//!
+//! ### Reproducibility
+//!
+//! Each saved design comes with a manifest, stored next to the output with the `.manifest` extension (e.g. `wsp.manifest`). It records the version of the crate, the command line and all parameters (including the seed and the defaults), the metric and the backend, and a hash of the input and output files. Regenerating the design with the same parameters must give the same output hash. The seed given with `--seed` is the only source of randomness of a run: the seeds of the generation of the candidates, of the choice of the first origin, of the k-means clustering and of the sub-sampling are derived from it with `wsp::derive_seed`, which applies the documented SplitMix64 scheme. Since seeds are derived this way, a given `--seed` draws different candidates and origins, and thus gives a different design, than in the earlier versions of the crate, where it seeded the generation directly.
+//!
+//! ### Random number generator
+//!
+//! By default, the candidates and the first origin are drawn with `SmallRng`, which is fast but whose algorithm may differ between platforms and versions of the `rand` crate. When the generator must be documented, e.g. for audited experimental designs, choose a portable one with `--rng chacha20` or `--rng pcg64`. The generator is recorded in the manifest. In the crate, use `PointSet::with_rng_algorithm` and pass `RngAlgorithm::seeded` generators to the `_with_rng` functions:
+//!
+//! ```bash
+//! $ wsp -n 5000 -m 20 -d 3.0 --rng chacha20
+//! ```
+//!
+//! ### Shell completion
+//!
+//! The `completions` subcommand prints a completion script for `bash`, `zsh`, `fish`, `powershell` or `elvish`, e.g.:
+//!
+//! ```bash
+//! $ wsp completions bash > ~/.local/share/bash-completion/completions/wsp
+//! ```
+//!
 //! ### More help
 //!
 //! Run `wsp -h` or `wsp --help` for more information about the arguments.
 
+use grid::Grid;
+use kdtree::KdTree;
+use quantized::QuantizedMatrix;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
 use serde::Serialize;
+use sphere::geodesic_distance_with;
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::error::Error;
+use std::time::Instant;
+
+mod anneal;
+mod backend;
+mod binary;
+mod bitset;
+mod borrowed;
+mod checkpoint;
+mod cluster;
+mod consumer;
+mod error;
+mod fixed;
+mod grid;
+mod index;
+mod kdtree;
+mod latin;
+mod manifest;
+mod merge;
+mod missing;
+mod nested;
+mod partition;
+mod profile;
+mod quantized;
+mod ranking;
+mod raw;
+mod reader;
+mod rng;
+mod selection;
+mod simplex;
+mod snapshot;
+mod sphere;
+mod stats;
+mod stream;
+mod subsample;
+mod summation;
+mod transform;
+mod voronoi;
+
+pub use anneal::wsp_annealed;
+pub use backend::Backend;
+pub use bitset::BitSet;
+pub use borrowed::BorrowedPointSet;
+pub use checkpoint::{adaptive_wsp_with_checkpoint, resume_adaptive_wsp};
+pub use cluster::{cluster_wsp, kmeans, kmeans_with_rng};
+pub use consumer::{DesignConsumer, RowMajor};
+pub use error::WspError;
+pub use fixed::{snap_to_fixed_point, MAX_FRACTIONAL_BITS};
+pub use latin::latinize;
+pub use manifest::{hash_file, Manifest};
+pub use missing::MissingValues;
+pub use nested::nested_wsp;
+pub use partition::partition_points;
+pub use profile::{distance_profile, sensitivity, Perturbation, Sensitivity};
+pub use quantized::{Quantization, QUANTIZED_NB_NEIGHBOURS};
+pub use ranking::rank_points;
+pub use raw::{read_points_binary, write_points_binary};
+pub use reader::{read_points_csv, CsvChunks};
+pub use rng::{derive_seed, RngAlgorithm, SeedStream, SeededRng};
+pub use selection::{min_distance, phi_p, wsp_restarts, wsp_seeds, SelectionCriterion};
+pub use simplex::{is_on_simplex, project_onto_simplex};
+pub use snapshot::Snapshot;
+pub use stats::{PhaseStats, RunStats};
+pub use stream::{stream_wsp, uniform_candidates, StreamingWsp};
+pub use subsample::{subsample, subsample_with_rng, subsampled_wsp, Subsampling};
+pub use summation::Summation;
+pub use transform::{Normalization, OutputScale};
+pub use voronoi::voronoi_weights;
+
+/// Approximate size, in bytes, of the coordinates of two tiles of points when computing the distance matrix.
+/// It should fit in the L2 cache.
+const TILE_BYTES: usize = 128 * 1024;
+/// Maximal number of points in a tile, bounding the size of the blocks of the distance matrix.
+const MAX_TILE: usize = 256;
+
+/// Seed of the generator drawing the first origin, unless a seed is given with [`PointSet::with_seed`].
+const ORIGIN_SEED: u64 = 10;
+/// Number of coordinates accumulated between two checks of the early-exit distance kernels.
+const EARLY_EXIT_CHUNK: usize = 8;
+
+/// Signature of a distance function between two points.
+type DistanceFn = dyn Fn(&[f64], &[f64]) -> f64;
+/// Signature of a removal observer: origin index, removed point index and distance between them.
+pub(crate) type RemovalObserver = dyn FnMut(usize, usize, f64) + Send + Sync;
 
 #[derive(Debug, Serialize)]
 struct Record {
     point: Vec<f64>,
 }
 
+/// Stores points in a CSV file, one point per row (or per column if `transpose` is set).
+/// See [`PointSet::save_in_csv`] to store the active points of a PointSet.
+///
+/// # Arguments
+///
+/// * `points` - The points to store.
+/// * `filepath` - The path to the file where to store the points.
+/// * `transpose` - Transpose the matrix in the output CSV
+pub fn write_points_csv(
+    points: &[Vec<f64>],
+    filepath: &str,
+    transpose: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut wrt = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_path(filepath)?;
+
+    if transpose {
+        let nb_dim = points.first().map_or(0, |p| p.len());
+        for d in 0..nb_dim {
+            wrt.serialize(Record {
+                point: points.iter().map(|p| p[d]).collect(),
+            })?;
+        }
+    } else {
+        for point in points.iter() {
+            wrt.serialize(Record {
+                point: point.clone(),
+            })?;
+        }
+    }
+    wrt.flush()?;
+    Ok(())
+}
+
 /// Internal representation of the WSP algorithm values.
 /// It is needed for the computation and to store information about the resulting point set.
+///
+/// The candidates (points and distances) and the state of a run (active and visited points) are
+/// stored together: concurrent runs on the same candidates each need their own PointSet. Once the
+/// algorithm has been executed, a PointSet is `Send` and `Sync`, so its results can be read from
+/// several threads (see `par_iter_remaining` with the `parallel` feature).
 pub struct PointSet {
     /// Points of the initial set
     pub points: Vec<Vec<f64>>,
-    /// All ditances between all points
+    /// All ditances between all points.
+    /// Only computed with the dense backend, empty otherwise
     pub distance_matrix: Vec<Vec<f64>>,
     /// If true, the point is still in the set. Otherwise, the point is considered as removed of the point set.
-    /// The user MUST only consider points with 'true' values as the only points in the resulting set
-    pub active: Vec<bool>,
+    /// The user MUST only consider points with 'true' values as the only points in the resulting set.
+    /// Stored with one bit per point
+    pub active: BitSet,
     /// Number of active points in the set
     pub nb_active: usize,
     /// For each point, the idx sorted increasingly with distance
-    /// to improve performance
+    /// to improve performance. Built lazily, before the first run of the algorithm
     idx_sort: Vec<Vec<usize>>,
+    /// If set, only the given number of closest neighbours are kept in each list of idx_sort.
+    /// The lists are extended on demand
+    nb_neighbours: Option<usize>,
     /// For each point, the idx in the idx_sort of the closest active point
     idx_active: Vec<usize>,
     /// Visited point to avoid looping over the same point several times => ensures that we clear all the space
-    visited: Vec<bool>,
+    visited: BitSet,
+    /// Points that must stay active, whatever their distance to the other points
+    fixed: Vec<bool>,
     /// Minimal distance between points in the point set
     d_min: f64,
     /// Maximal distance between points in the point set
     d_max: f64,
+    /// Distance used between points
+    metric: Metric,
+    /// Algorithm used to find the neighbours of the origins
+    backend: Backend,
+    /// Spatial index of the points, for the tree-based backends
+    kdtree: Option<KdTree>,
+    /// Spatial index of the points, for the grid backend. Built at the start of each run, with cells of
+    /// the size of the minimal distance
+    grid: Option<Grid>,
+    /// Called each time a point is removed by the algorithm
+    observer: Option<Box<RemovalObserver>>,
+    /// Transform applied to the original points, if they have been normalized
+    normalization: Option<Normalization>,
+    /// Number of fractional bits of the coordinates, if they have been snapped to fixed-point
+    fixed_point: Option<u32>,
+    /// Summation algorithm of the distances
+    summation: Summation,
+    /// Quantized distance matrix, replacing `distance_matrix` when set
+    quantized: Option<QuantizedMatrix>,
+    /// Precision of the quantized distances, applied to the matrix of the dense backend
+    quantization: Option<Quantization>,
+    /// Algorithm and seed of the generator drawing the first origin
+    rng_algorithm: RngAlgorithm,
+    origin_seed: u64,
+    /// Timings and counters of the phases of the work done on the PointSet
+    stats: PhaseStats,
 }
 
 impl PointSet {
     /// Creates a 'PointSet' from an already initialised vector of points.
+    /// Missing coordinates (NaN) are handled with [`MissingValues::Skip`], and two points without any
+    /// known coordinate in common are never too close to each other. To reject them or choose another
+    /// policy, see [`PointSet::init_from_preset_with_missing`].
     ///
     /// # Arguments
     ///
@@ -164,26 +560,326 @@ impl PointSet {
     /// let poinset = wsp::PointSet::init_from_preset(points); // Give ownership
     /// ```
     pub fn init_from_preset(points: Vec<Vec<f64>>) -> PointSet {
-        // First compute the distance matrix, then move "points" to the
-        // output structure
-        let (distance_matrix, d_min, d_max) = PointSet::compute_distance_matrix(&points, None);
+        PointSet::init_from_preset_with_metric(points, Metric::Manhattan)
+    }
+
+    /// Creates a 'PointSet' from an already initialised vector of points, using the given metric
+    /// to compute the distance between points.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The pre-initialised set of points.
+    /// * `metric` - The distance used between the points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points: Vec<Vec<f64>> = vec![vec![1.0, 0.0, 1.0], vec![0.5, 0.5, 0.5]];
+    /// let poinset = wsp::PointSet::init_from_preset_with_metric(points, wsp::Metric::Euclidean);
+    /// ```
+    pub fn init_from_preset_with_metric(points: Vec<Vec<f64>>, metric: Metric) -> PointSet {
+        let nb_dim = points.first().map_or(0, |p| p.len());
+        let backend = Backend::auto(points.len(), nb_dim);
+        PointSet::init_from_preset_with_backend(points, metric, backend)
+    }
+
+    /// Creates a 'PointSet' from an already initialised vector of points, using the given metric
+    /// and backend. Other constructors choose the backend with [`Backend::auto`].
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The pre-initialised set of points.
+    /// * `metric` - The distance used between the points.
+    /// * `backend` - The algorithm used to find the neighbours of the origins.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points: Vec<Vec<f64>> = vec![vec![1.0, 0.0, 1.0], vec![0.5, 0.5, 0.5]];
+    /// let poinset = wsp::PointSet::init_from_preset_with_backend(
+    ///     points,
+    ///     wsp::Metric::Manhattan,
+    ///     wsp::Backend::KdTree,
+    /// );
+    /// ```
+    pub fn init_from_preset_with_backend(
+        points: Vec<Vec<f64>>,
+        metric: Metric,
+        backend: Backend,
+    ) -> PointSet {
+        PointSet::init_with_summation(points, metric, backend, Summation::Naive, None)
+    }
 
-        let mut p = PointSet {
+    pub(crate) fn init_with_summation(
+        points: Vec<Vec<f64>>,
+        metric: Metric,
+        backend: Backend,
+        summation: Summation,
+        quantization: Option<Quantization>,
+    ) -> PointSet {
+        // Only the dense backend computes the distances of points with missing values
+        let has_missing = points.iter().flatten().any(|x| x.is_nan());
+        let mut p = if backend != Backend::Dense && !has_missing {
+            PointSet::init_matrix_free(points, metric, backend)
+        } else {
+            // First compute the distance matrix, then move "points" to the
+            // output structure
+            let start = Instant::now();
+            let skipping = move |p1: &[f64], p2: &[f64]| missing::skipping_distance(metric, p1, p2);
+            let exact = move |p1: &[f64], p2: &[f64]| metric.distance_with(p1, p2, summation);
+            let distance: &DistanceFn = if has_missing { &skipping } else { &exact };
+            let nb_points = points.len();
+            let mut p = match quantization {
+                Some(quantization) => {
+                    let (quantized, d_min, d_max) =
+                        QuantizedMatrix::from_points(&points, distance, metric, quantization);
+                    let mut p = PointSet::init_from_distance_matrix(
+                        points,
+                        Vec::new(),
+                        d_min,
+                        d_max,
+                        metric,
+                    );
+                    p.quantized = Some(quantized);
+                    p
+                }
+                None => {
+                    let (distance_matrix, d_min, d_max) =
+                        PointSet::compute_distance_matrix(&points, Some(distance));
+                    PointSet::init_from_distance_matrix(
+                        points,
+                        distance_matrix,
+                        d_min,
+                        d_max,
+                        metric,
+                    )
+                }
+            };
+            p.stats.matrix_time = start.elapsed().as_secs_f64();
+            p.stats.nb_distances = nb_points * nb_points.saturating_sub(1) / 2;
+            p
+        };
+        p.summation = summation;
+        p.quantization = quantization;
+        p
+    }
+
+    /// Dense initialisation from an already computed distance matrix.
+    fn init_from_distance_matrix(
+        points: Vec<Vec<f64>>,
+        distance_matrix: Vec<Vec<f64>>,
+        d_min: f64,
+        d_max: f64,
+        metric: Metric,
+    ) -> PointSet {
+        PointSet {
             distance_matrix,
-            active: vec![true; points.len()],
+            active: BitSet::new(points.len(), true),
             nb_active: points.len(),
-            idx_sort: Vec::with_capacity(points.len()),
+            idx_sort: Vec::new(),
+            nb_neighbours: None,
             // Start at 1 because closest is itself
             idx_active: vec![1; points.len()],
-            visited: vec![false; points.len()],
+            visited: BitSet::new(points.len(), false),
+            fixed: vec![false; points.len()],
             points,
             d_max,
             d_min,
+            metric,
+            backend: Backend::Dense,
+            kdtree: None,
+            grid: None,
+            observer: None,
+            normalization: None,
+            fixed_point: None,
+            summation: Summation::Naive,
+            rng_algorithm: RngAlgorithm::Small,
+            origin_seed: ORIGIN_SEED,
+            quantized: None,
+            quantization: None,
+            stats: PhaseStats::default(),
+        }
+    }
+
+    /// Matrix-free initialisation. The exact minimal and maximal distances are unknown,
+    /// so they are bounded by 0 and the diameter of the bounding box of the points.
+    fn init_matrix_free(points: Vec<Vec<f64>>, metric: Metric, backend: Backend) -> PointSet {
+        let d_max = diameter_bound(&points, metric);
+
+        let start = Instant::now();
+        let kdtree = match backend {
+            Backend::Grid => None,
+            _ => Some(KdTree::new(&points)),
+        };
+        let stats = PhaseStats {
+            index_time: start.elapsed().as_secs_f64(),
+            nb_index_builds: kdtree.is_some() as usize,
+            ..Default::default()
         };
-        p.compute_closest_idx();
+
+        PointSet {
+            distance_matrix: Vec::new(),
+            active: BitSet::new(points.len(), true),
+            nb_active: points.len(),
+            idx_sort: Vec::new(),
+            nb_neighbours: None,
+            idx_active: Vec::new(),
+            visited: BitSet::new(points.len(), false),
+            fixed: vec![false; points.len()],
+            kdtree,
+            grid: None,
+            observer: None,
+            normalization: None,
+            fixed_point: None,
+            summation: Summation::Naive,
+            rng_algorithm: RngAlgorithm::Small,
+            origin_seed: ORIGIN_SEED,
+            quantized: None,
+            quantization: None,
+            stats,
+            points,
+            d_max,
+            d_min: 0.0,
+            metric,
+            backend,
+        }
+    }
+
+    /// Returns the same PointSet using another backend. The internal structures are rebuilt
+    /// if the backend changes. The state of the points (active, fixed...) is kept.
+    /// Points with missing values (NaN) always use the dense backend, see [`MissingValues::Skip`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points = wsp::PointSet::init_from_random(100, 10, 51).with_backend(wsp::Backend::KdTree);
+    /// assert_eq!(points.backend(), wsp::Backend::KdTree);
+    /// ```
+    pub fn with_backend(self, backend: Backend) -> PointSet {
+        if backend == self.backend {
+            return self;
+        }
+        let summation = self.summation;
+        self.rebuild(backend, summation)
+    }
+
+    /// Returns the same PointSet using another summation algorithm for the distances. The internal
+    /// structures are rebuilt if the algorithm changes. The state of the points (active, fixed...) is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points = wsp::PointSet::init_from_random(100, 10, 51).with_summation(wsp::Summation::Neumaier);
+    /// assert_eq!(points.summation(), wsp::Summation::Neumaier);
+    /// ```
+    pub fn with_summation(self, summation: Summation) -> PointSet {
+        if summation == self.summation {
+            return self;
+        }
+        let backend = self.backend;
+        self.rebuild(backend, summation)
+    }
+
+    /// Returns the summation algorithm of the distances.
+    pub fn summation(&self) -> Summation {
+        self.summation
+    }
+
+    /// Returns the same PointSet drawing the first origin of the algorithm with a generator of the given
+    /// algorithm, instead of `SmallRng`. The seed of the generator does not change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 2, 51)
+    ///     .with_rng_algorithm(wsp::RngAlgorithm::ChaCha20);
+    /// assert_eq!(points.rng_algorithm(), wsp::RngAlgorithm::ChaCha20);
+    /// wsp::wsp(&mut points, 0.05);
+    /// ```
+    pub fn with_rng_algorithm(mut self, rng_algorithm: RngAlgorithm) -> PointSet {
+        self.rng_algorithm = rng_algorithm;
+        self
+    }
+
+    /// Returns the algorithm of the generator drawing the first origin of the algorithm.
+    pub fn rng_algorithm(&self) -> RngAlgorithm {
+        self.rng_algorithm
+    }
+
+    /// Returns the seed of the generator drawing the first origin of the algorithm.
+    pub fn origin_seed(&self) -> u64 {
+        self.origin_seed
+    }
+
+    /// Returns the same PointSet drawing the first origin of the algorithm from the origin stream of the
+    /// seed of the run (see [`derive_seed`]), instead of a fixed internal seed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wsp::{derive_seed, SeedStream};
+    ///
+    /// let seed = 51;
+    /// let mut points =
+    ///     wsp::PointSet::init_from_random(1000, 2, derive_seed(seed, SeedStream::Generation))
+    ///         .with_seed(seed);
+    /// wsp::wsp(&mut points, 0.05);
+    /// ```
+    pub fn with_seed(mut self, seed: u64) -> PointSet {
+        self.origin_seed = derive_seed(seed, SeedStream::Origin);
+        self
+    }
+
+    /// Rebuilds the internal structures, keeping the state of the points.
+    fn rebuild(self, backend: Backend, summation: Summation) -> PointSet {
+        let mut p = PointSet::init_with_summation(
+            self.points,
+            self.metric,
+            backend,
+            summation,
+            self.quantization,
+        );
+        p.active = self.active;
+        p.nb_active = self.nb_active;
+        p.visited = self.visited;
+        p.fixed = self.fixed;
+        p.observer = self.observer;
+        p.normalization = self.normalization;
+        p.fixed_point = self.fixed_point;
+        p.rng_algorithm = self.rng_algorithm;
+        p.origin_seed = self.origin_seed;
+        p.stats += self.stats;
         p
     }
 
+    /// Returns the timings and counters of the phases of the work done on the PointSet since its creation:
+    /// distance matrix, sorting of the neighbours, spatial index and selection loop.
+    pub fn stats(&self) -> PhaseStats {
+        self.stats
+    }
+
+    /// Returns the backend used to find the neighbours of the origins.
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// Returns the distance between the points at indexes `i` and `j`.
+    pub fn distance(&self, i: usize, j: usize) -> f64 {
+        match self.backend {
+            Backend::Dense if self.quantized.is_none() => self.distance_matrix[i][j],
+            _ => self.exact_distance(i, j),
+        }
+    }
+
+    /// Computes the distance between the points at indexes `i` and `j` from their coordinates.
+    fn exact_distance(&self, i: usize, j: usize) -> f64 {
+        let (p1, p2) = (&self.points[i], &self.points[j]);
+        if p1.iter().chain(p2.iter()).any(|x| x.is_nan()) {
+            return missing::skipping_distance(self.metric, p1, p2);
+        }
+        self.metric.distance_with(p1, p2, self.summation)
+    }
+
     /// Creates a 'PointSet' using a random initialisation of the points following a uniform distribution.
     ///
     /// # Arguments
@@ -200,9 +896,32 @@ impl PointSet {
     /// let poinset = wsp::PointSet::init_from_random(100, 10, 51); // Give ownership
     /// ```
     pub fn init_from_random(nb_points: usize, nb_dim: usize, seed: u64) -> PointSet {
-        let mut points: Vec<Vec<f64>> = Vec::with_capacity(nb_points);
+        PointSet::init_from_random_with_rng(nb_points, nb_dim, &mut SmallRng::seed_from_u64(seed))
+    }
 
-        let mut rng = SmallRng::seed_from_u64(seed);
+    /// Creates a 'PointSet' using a random initialisation of the points following a uniform distribution,
+    /// drawn from a caller-supplied random number generator.
+    ///
+    /// # Arguments
+    ///
+    /// * `nb_points` - The number of points in the set before running WSP.
+    /// * `nb_dim` - The dimension of the points.
+    /// * `rng` - The random number generator used for the uniform sampling of the coordinates of the points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::SeedableRng;
+    ///
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(51);
+    /// let poinset = wsp::PointSet::init_from_random_with_rng(100, 10, &mut rng);
+    /// ```
+    pub fn init_from_random_with_rng<R: Rng + ?Sized>(
+        nb_points: usize,
+        nb_dim: usize,
+        rng: &mut R,
+    ) -> PointSet {
+        let mut points: Vec<Vec<f64>> = Vec::with_capacity(nb_points);
 
         // Generate random points
         for _ in 0..nb_points {
@@ -216,134 +935,527 @@ impl PointSet {
         PointSet::init_from_preset(points)
     }
 
+    /// Creates a new 'PointSet' containing only the points at the given indexes.
+    /// The distances are copied from the current distance matrix instead of being recomputed.
+    fn subset(&self, idxs: &[usize]) -> PointSet {
+        let points: Vec<Vec<f64>> = idxs.iter().map(|&i| self.points[i].clone()).collect();
+        if self.backend != Backend::Dense {
+            let mut p = PointSet::init_matrix_free(points, self.metric, self.backend);
+            p.fixed = idxs.iter().map(|&i| self.fixed[i]).collect();
+            p.rng_algorithm = self.rng_algorithm;
+            p.origin_seed = self.origin_seed;
+            p.quantization = self.quantization;
+            return p;
+        }
+        let distance_matrix: Vec<Vec<f64>> = idxs
+            .iter()
+            .map(|&i| idxs.iter().map(|&j| self.distance(i, j)).collect())
+            .collect();
+        let mut d_min: f64 = f64::MAX;
+        let mut d_max: f64 = 0.0;
+        for (i, row) in distance_matrix.iter().enumerate() {
+            for &d in row[i + 1..].iter() {
+                d_min = d_min.min(d);
+                d_max = d_max.max(d);
+            }
+        }
+
+        let mut p =
+            PointSet::init_from_distance_matrix(points, distance_matrix, d_min, d_max, self.metric);
+        p.nb_neighbours = self.nb_neighbours;
+        p.fixed = idxs.iter().map(|&i| self.fixed[i]).collect();
+        p.rng_algorithm = self.rng_algorithm;
+        p.origin_seed = self.origin_seed;
+        match self.quantization() {
+            Some(quantization) => p.with_quantization(quantization),
+            None => p,
+        }
+    }
+
+    /// Forces the points at the given indexes to stay active in the next runs of the algorithm,
+    /// whatever their distance to the other points. Previously fixed points stay fixed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+    /// points.set_fixed(&[0, 1]);
+    /// wsp::wsp(&mut points, 3.0);
+    /// assert!(points.active[0] && points.active[1]);
+    /// ```
+    pub fn set_fixed(&mut self, idxs: &[usize]) {
+        idxs.iter().for_each(|&i| self.fixed[i] = true);
+    }
+
+    /// Releases all the fixed points of the PointSet.
+    pub fn clear_fixed(&mut self) {
+        self.fixed = vec![false; self.points.len()];
+    }
+
+    /// Registers a function called each time the algorithm removes a point, with the index of the
+    /// current origin, the index of the removed point and the distance between them.
+    /// Replaces the previous observer, if any.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let removed = Arc::new(Mutex::new(Vec::new()));
+    /// let log = Arc::clone(&removed);
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+    /// points.set_observer(move |_origin, victim, _distance| log.lock().unwrap().push(victim));
+    /// wsp::wsp(&mut points, 3.0);
+    /// assert_eq!(removed.lock().unwrap().len(), 1000 - points.nb_active);
+    /// ```
+    pub fn set_observer<F>(&mut self, observer: F)
+    where
+        F: FnMut(usize, usize, f64) + Send + Sync + 'static,
+    {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Removes the observer of the PointSet.
+    pub fn clear_observer(&mut self) {
+        self.observer = None;
+    }
+
+    /// Converts a [`DMin`] into an absolute distance for this PointSet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points: Vec<Vec<f64>> = vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![3.0, 0.0]];
+    /// let pointset = wsp::PointSet::init_from_preset(points);
+    /// assert_eq!(pointset.resolve_d_min(wsp::DMin::Absolute(0.5)), 0.5);
+    /// assert_eq!(pointset.resolve_d_min(wsp::DMin::Relative(0.5)), 1.5);
+    /// assert_eq!(pointset.resolve_d_min(wsp::DMin::RelativeMean(0.5)), 1.0);
+    /// ```
+    pub fn resolve_d_min(&self, d_min: DMin) -> f64 {
+        match d_min {
+            DMin::Absolute(d) => d,
+            DMin::Relative(fraction) => fraction * self.d_max,
+            DMin::RelativeMean(fraction) => fraction * self.mean_distance(),
+        }
+    }
+
+    /// Mean distance between two distinct points of the PointSet.
+    fn mean_distance(&self) -> f64 {
+        let nb_points = self.points.len();
+        if nb_points < 2 {
+            return 0.0;
+        }
+        let sum: f64 = (0..nb_points)
+            .map(|i| (i + 1..nb_points).map(|j| self.distance(i, j)).sum::<f64>())
+            .sum();
+        sum / (nb_points * (nb_points - 1) / 2) as f64
+    }
+
+    /// Returns the metric used to compute the distance between points.
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
     fn reset_reseach_params(&mut self) {
         self.nb_active = self.points.len();
-        self.active = vec![true; self.nb_active];
-        self.idx_active = vec![1; self.nb_active];
-        self.visited = vec![false; self.nb_active];
+        self.active = BitSet::new(self.nb_active, true);
+        self.reset_visited();
+    }
+
+    /// Resets the search state but keeps the current active points.
+    fn reset_visited(&mut self) {
+        let nb_points = self.points.len();
+        if self.backend == Backend::Dense {
+            self.idx_active = vec![1; nb_points];
+        }
+        self.visited = BitSet::new(nb_points, false);
     }
 
     fn compute_closest_idx(&mut self) {
-        for i in 0..self.nb_active {
-            let mut idxs: Vec<usize> = (0..self.nb_active).collect();
-            idxs.sort_by(|&a, &b| {
-                self.distance_matrix[i][a]
-                    .partial_cmp(&self.distance_matrix[i][b])
-                    .unwrap()
-            });
-            self.idx_sort.push(idxs);
+        let start = Instant::now();
+        let nb_points = self.points.len();
+        let len = self
+            .neighbour_list_len()
+            .map_or(nb_points, |k| (k + 1).min(nb_points));
+        self.idx_sort = (0..nb_points)
+            .map(|i| self.sorted_neighbours(i, len))
+            .collect();
+        self.stats.sort_time += start.elapsed().as_secs_f64();
+        self.stats.nb_sorted_lists += nb_points;
+    }
+
+    /// Returns the `len` closest points of point `i`, sorted increasingly with distance.
+    /// Ties are broken with the index of the points, except for the point itself that always comes first.
+    /// This total order ensures that a shorter list is always a prefix of a longer one.
+    fn sorted_neighbours(&self, i: usize, len: usize) -> Vec<usize> {
+        let row: Cow<[f64]> = match self.quantized {
+            None => Cow::Borrowed(&self.distance_matrix[i]),
+            // Neighbours are sorted by exact distance
+            Some(_) => (0..self.points.len())
+                .map(|j| self.exact_distance(i, j))
+                .collect(),
+        };
+        // Distances between points without any known coordinate in common (NaN) come last
+        let cmp = |a: &usize, b: &usize| {
+            row[*a]
+                .total_cmp(&row[*b])
+                .then((*a != i).cmp(&(*b != i)))
+                .then(a.cmp(b))
+        };
+        let mut idxs: Vec<usize> = (0..self.points.len()).collect();
+        if len < idxs.len() {
+            idxs.select_nth_unstable_by(len - 1, cmp);
+            idxs.truncate(len);
+            idxs.shrink_to_fit();
         }
+        idxs.sort_unstable_by(cmp);
+        idxs
+    }
+
+    /// Doubles the number of neighbours kept for point `i`.
+    fn extend_neighbours(&mut self, i: usize) {
+        let start = Instant::now();
+        let len = (2 * self.idx_sort[i].len()).min(self.points.len());
+        self.idx_sort[i] = self.sorted_neighbours(i, len);
+        self.stats.sort_time += start.elapsed().as_secs_f64();
+        self.stats.nb_sorted_lists += 1;
+    }
+
+    /// Only keeps the `k` closest neighbours of each point in the sorted neighbour lists used by the
+    /// dense backend, reducing their memory from O(n²) to O(nk). When the algorithm reaches the end of
+    /// a list, the list is extended on demand.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51).with_nb_neighbours(50);
+    /// wsp::wsp(&mut points, 3.0);
+    /// ```
+    pub fn with_nb_neighbours(mut self, k: usize) -> PointSet {
+        self.nb_neighbours = Some(k.max(1));
+        self.idx_sort = Vec::new();
+        self
     }
 
+    /// Computes the symmetric distance matrix of the points, with the minimal and maximal distances.
     fn compute_distance_matrix(
         points: &[Vec<f64>],
-        distance_algo: Option<&dyn Fn(&[f64], &[f64]) -> f64>,
+        distance_algo: Option<&DistanceFn>,
     ) -> (Vec<Vec<f64>>, f64, f64) {
         let nb_points = points.len();
         let mut distance_matrix = vec![vec![0.0f64; nb_points]; nb_points];
+        let (d_min, d_max) = PointSet::for_each_distance(points, distance_algo, |i, j, d| {
+            distance_matrix[i][j] = d;
+            distance_matrix[j][i] = d;
+        });
+        (distance_matrix, d_min, d_max)
+    }
+
+    /// Computes the distance of each pair of points `i < j` and hands it to `visit`, then returns the
+    /// minimal and maximal distances. The pairs are visited by tiles of points: the coordinates of two tiles
+    /// are copied in contiguous buffers small enough to stay in the L2 cache while all their pairs are
+    /// computed, and the corresponding blocks of a matrix filled by `visit` are small enough to stay in
+    /// the cache as well.
+    pub(crate) fn for_each_distance<F: FnMut(usize, usize, f64)>(
+        points: &[Vec<f64>],
+        distance_algo: Option<&DistanceFn>,
+        mut visit: F,
+    ) -> (f64, f64) {
+        let nb_points = points.len();
+        let nb_dim = points.first().map_or(0, |p| p.len());
         let mut dmin: f64 = f64::MAX;
         let mut dmax: f64 = 0.0;
-        for i in 0..nb_points {
-            for j in i + 1..nb_points {
-                distance_matrix[i][j] = match distance_algo {
-                    Some(algo) => algo(&points[i], &points[j]),
-                    None => manhattan_distance(&points[i], &points[j]),
-                };
-
-                distance_matrix[j][i] = distance_matrix[i][j]; // Primitive type copy
-                dmin = dmin.min(distance_matrix[i][j]);
-                dmax = dmax.max(distance_matrix[i][j]);
+        if nb_dim == 0 || points.iter().any(|p| p.len() != nb_dim) {
+            // Points of mixed dimensions cannot be stored contiguously
+            for i in 0..nb_points {
+                for j in i + 1..nb_points {
+                    let d = match distance_algo {
+                        Some(algo) => algo(&points[i], &points[j]),
+                        None => manhattan_distance(&points[i], &points[j]),
+                    };
+                    visit(i, j, d);
+                    dmin = dmin.min(d);
+                    dmax = dmax.max(d);
+                }
             }
+            return (dmin.min(dmax), dmax);
+        }
+
+        let tile = (TILE_BYTES / (2 * nb_dim * std::mem::size_of::<f64>())).clamp(8, MAX_TILE);
+        let flatten = |start: usize, end: usize| -> Vec<f64> {
+            points[start..end]
+                .iter()
+                .flat_map(|p| p.iter().copied())
+                .collect()
+        };
+        for start_i in (0..nb_points).step_by(tile) {
+            let end_i = (start_i + tile).min(nb_points);
+            let tile_i = flatten(start_i, end_i);
+            for start_j in (start_i..nb_points).step_by(tile) {
+                let end_j = (start_j + tile).min(nb_points);
+                let tile_j = flatten(start_j, end_j);
+                for (i, p1) in (start_i..end_i).zip(tile_i.chunks_exact(nb_dim)) {
+                    // Only the upper triangle of the diagonal tiles
+                    let first_j = start_j.max(i + 1);
+                    let p2s = tile_j.chunks_exact(nb_dim).skip(first_j - start_j);
+                    for (j, p2) in (first_j..end_j).zip(p2s) {
+                        let d = match distance_algo {
+                            Some(algo) => algo(p1, p2),
+                            None => manhattan_distance(p1, p2),
+                        };
+                        visit(i, j, d);
+                        dmin = dmin.min(d);
+                        dmax = dmax.max(d);
+                    }
+                }
+            }
+        }
+        // Without any pair of points, both distances are 0
+        (dmin.min(dmax), dmax)
+    }
+
+    /// Stores a PointSet in a CSV file. This will store in a matrix form the active points in the PointSet.
+    /// Each row represents an active point, and each column a dimension in the space.
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path to the file where to store the PointSet points.
+    /// * `transpose` - Transpose the matrix in the output CSV
+    ///
+    /// # Example
+    ///
+    /// The following code snippet stores the PointSet *before* running WSP
+    /// ```
+    /// let points = wsp::PointSet::init_from_random(100, 10, 51);
+    ///
+    /// if let Err(err) = points.save_in_csv("wsp.csv", false) {
+    ///     eprintln!("Error writing in CSV: {}", err);
+    ///     std::process::exit(1);
+    /// }
+    /// ```
+    pub fn save_in_csv(&self, filepath: &str, transpose: bool) -> Result<(), Box<dyn Error>> {
+        write_points_csv(&self.get_remaining(), filepath, transpose)
+    }
+
+    /// Stores the state of all the points of the PointSet in a CSV file, with one row `candidate,active`
+    /// per point, where `active` is 1 for the points remaining after WSP and 0 for the removed points.
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path to the file where to store the mask.
+    pub fn save_mask_in_csv(&self, filepath: &str) -> Result<(), Box<dyn Error>> {
+        let mut wrt = csv::Writer::from_path(filepath)?;
+        wrt.write_record(["candidate", "active"])?;
+        for (candidate, active) in self.active.iter().enumerate() {
+            wrt.write_record([candidate.to_string(), (active as u8).to_string()])?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+
+    /// Returns a new vector containing only the active points of the PointSet.
+    ///
+    /// # Example
+    ///
+    /// The following code snippet stores the PointSet *before* running WSP
+    /// ```
+    /// // Generates the initial set
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+    ///
+    /// // Only keep distant enough points
+    /// let d_min = 3.0;
+    /// wsp::wsp(&mut points, d_min);
+    ///
+    /// // Iterate over the remaining points
+    /// for valid_point in points.get_remaining() {
+    ///     println!("{:?}", valid_point);
+    /// }
+    /// ```
+    pub fn get_remaining(&self) -> Vec<Vec<f64>> {
+        self.iter_remaining().cloned().collect()
+    }
+
+    /// Returns an iterator over the active points of the PointSet, without copying them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+    /// wsp::wsp(&mut points, 3.0);
+    /// assert_eq!(points.iter_remaining().count(), points.nb_active);
+    /// ```
+    pub fn iter_remaining(&self) -> impl Iterator<Item = &Vec<f64>> + Clone {
+        self.active.iter_ones().map(|i| &self.points[i])
+    }
+
+    /// Returns a parallel iterator over the active points of the PointSet, without copying them.
+    /// A PointSet is `Send` and `Sync`, so it can also be shared by reference between threads once
+    /// the algorithm has been executed.
+    ///
+    /// Requires the `parallel` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rayon::prelude::*;
+    ///
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+    /// wsp::wsp(&mut points, 3.0);
+    /// let sums: Vec<f64> = points.par_iter_remaining().map(|p| p.iter().sum()).collect();
+    /// assert_eq!(sums.len(), points.nb_active);
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn par_iter_remaining(&self) -> impl rayon::iter::ParallelIterator<Item = &Vec<f64>> {
+        use rayon::prelude::*;
+
+        self.points
+            .par_iter()
+            .enumerate()
+            .filter(|(i, _)| self.active[*i])
+            .map(|(_, point)| point)
+    }
+}
+
+/// Minimal distance between the points of a PointSet.
+/// Absolute distances are meaningless before inspecting the scale of the data,
+/// so the minimal distance can also be given relatively to the distances between the candidate points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DMin {
+    /// Absolute distance.
+    Absolute(f64),
+    /// Fraction of the maximal distance between two points of the PointSet.
+    Relative(f64),
+    /// Fraction of the mean distance between two points of the PointSet.
+    RelativeMean(f64),
+}
+
+impl From<f64> for DMin {
+    fn from(d_min: f64) -> DMin {
+        DMin::Absolute(d_min)
+    }
+}
+
+/// Distance used to compare points of a PointSet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// l1 distance. It provides better separation in high dimensional spaces than the l2 distance.
+    #[default]
+    Manhattan,
+    /// l2 distance.
+    Euclidean,
+    /// Angle between two points of the unit hypersphere (great-circle distance).
+    Geodesic,
+}
+
+impl Metric {
+    /// Returns the distance between two points according to the metric.
+    pub fn distance(&self, p1: &[f64], p2: &[f64]) -> f64 {
+        match self {
+            Metric::Manhattan => manhattan_distance(p1, p2),
+            Metric::Euclidean => distance_sq(p1, p2).sqrt(),
+            Metric::Geodesic => geodesic_distance_with(p1, p2, Summation::Naive),
         }
-        (distance_matrix, dmin, dmax)
     }
 
-    /// Stores a PointSet in a CSV file. This will store in a matrix form the active points in the PointSet.
-    /// Each row represents an active point, and each column a dimension in the space.
-    ///
-    /// # Arguments
-    ///
-    /// * `filepath` - The path to the file where to store the PointSet points.
-    /// * `transpose` - Transpose the matrix in the output CSV
+    /// Returns the distance between two points according to the metric, summing the
+    /// per-dimension terms with the given algorithm.
     ///
     /// # Example
     ///
-    /// The following code snippet stores the PointSet *before* running WSP
     /// ```
-    /// let points = wsp::PointSet::init_from_random(100, 10, 51);
+    /// use wsp::{Metric, Summation};
     ///
-    /// if let Err(err) = points.save_in_csv("wsp.csv", false) {
-    ///     eprintln!("Error writing in CSV: {}", err);
-    ///     std::process::exit(1);
-    /// }
+    /// let p1 = [1e16, 1.0, 1.0];
+    /// let p2 = [0.0, 0.0, 0.0];
+    /// assert_eq!(Metric::Manhattan.distance_with(&p1, &p2, Summation::Neumaier), 1e16 + 2.0);
     /// ```
-    pub fn save_in_csv(&self, filepath: &str, transpose: bool) -> Result<(), Box<dyn Error>> {
-        let mut wrt = csv::WriterBuilder::new()
-            .has_headers(false)
-            .from_path(filepath)?;
-
-        // Use star notation just to show that we understand it
-        let points = if transpose {
-            let mut transposed: Vec<Vec<f64>> =
-                vec![Vec::with_capacity(self.nb_active); self.points[0].len()];
-            for (i, point) in (*self.points).iter().enumerate() {
-                if self.active[i] {
-                    for i in 0..point.len() {
-                        transposed[i].push(point[i]);
-                    }
-                }
+    pub fn distance_with(&self, p1: &[f64], p2: &[f64], summation: Summation) -> f64 {
+        match (self, summation) {
+            (_, Summation::Naive) => self.distance(p1, p2),
+            (Metric::Manhattan, _) => {
+                summation.sum(p1.iter().zip(p2.iter()).map(|(x1, x2)| (x1 - x2).abs()))
             }
-            transposed
-        } else {
-            self.points
-                .iter()
-                .enumerate()
-                .filter(|(i, _)| self.active[*i])
-                .to_owned()
-                .unzip::<usize, &Vec<f64>, Vec<usize>, Vec<&Vec<f64>>>()
-                .1
-                .iter()
-                .map(|x| x.to_owned().to_owned())
-                .collect()
-        };
-        for point in points.iter() {
-            wrt.serialize(Record {
-                point: point.clone(),
-            })?;
+            (Metric::Euclidean, _) => summation
+                .sum(
+                    p1.iter()
+                        .zip(p2.iter())
+                        .map(|(x1, x2)| (x1 - x2) * (x1 - x2)),
+                )
+                .sqrt(),
+            (Metric::Geodesic, _) => geodesic_distance_with(p1, p2, summation),
         }
-        Ok(())
     }
 
-    /// Returns a new vector containing only the active points of the PointSet.
+    /// Returns the distance between two points if it is strictly lower than `limit`, or None otherwise,
+    /// e.g. to check whether two points are closer than the minimal distance. With the naive summation
+    /// of the l1 and l2 distances, the accumulation stops as soon as the partial sum exceeds the limit,
+    /// which skips most of the coordinates of far-apart points in high dimensions.
     ///
     /// # Example
     ///
-    /// The following code snippet stores the PointSet *before* running WSP
     /// ```
-    /// // Generates the initial set
-    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
-    ///
-    /// // Only keep distant enough points
-    /// let d_min = 3.0;
-    /// wsp::wsp(&mut points, d_min);
+    /// use wsp::{Metric, Summation};
     ///
-    /// // Iterate over the remaining points
-    /// for valid_point in points.get_remaining() {
-    ///     println!("{:?}", valid_point);
-    /// }
+    /// let p1 = [0.0; 100];
+    /// let p2 = [1.0; 100];
+    /// assert_eq!(Metric::Manhattan.distance_below(&p1, &p2, 10.0, Summation::Naive), None);
+    /// assert_eq!(Metric::Euclidean.distance_below(&p1, &p2, 10.5, Summation::Naive), Some(10.0));
     /// ```
-    pub fn get_remaining(&self) -> Vec<Vec<f64>> {
-        let mut points: Vec<Vec<f64>> = Vec::with_capacity(self.nb_active);
-        for i in 0..self.points.len() {
-            if self.active[i] {
-                points.push(self.points[i].clone());
+    pub fn distance_below(
+        &self,
+        p1: &[f64],
+        p2: &[f64],
+        limit: f64,
+        summation: Summation,
+    ) -> Option<f64> {
+        // Distances are never negative
+        if limit <= 0.0 || limit.is_nan() {
+            return None;
+        }
+        let dist = match (self, summation) {
+            (Metric::Manhattan, Summation::Naive) => {
+                partial_sum(p1, p2, limit, |x1, x2| (x1 - x2).abs())?
             }
+            (Metric::Euclidean, Summation::Naive) => {
+                // Margin such that a larger sum of squares always has a larger square root
+                let limit_sq = limit * limit * (1.0 + 4.0 * f64::EPSILON);
+                partial_sum(p1, p2, limit_sq, |x1, x2| (x1 - x2) * (x1 - x2))?.sqrt()
+            }
+            _ => self.distance_with(p1, p2, summation),
+        };
+        Some(dist).filter(|&d| d < limit)
+    }
+
+    /// Lower bound of the distance between two points whose coordinates differ
+    /// by `delta` along one of the dimensions.
+    fn axis_lower_bound(&self, delta: f64) -> f64 {
+        match self {
+            Metric::Manhattan | Metric::Euclidean => delta,
+            // The chord between two points of the unit hypersphere is at least `delta`
+            Metric::Geodesic => 2.0 * (delta / 2.0).min(1.0).asin(),
+        }
+    }
+}
+
+/// Upper bound of the distance between two of the points: the diagonal of their bounding box.
+fn diameter_bound(points: &[Vec<f64>], metric: Metric) -> f64 {
+    let nb_dim = points.first().map_or(0, |p| p.len());
+    let mut lower = vec![f64::MAX; nb_dim];
+    let mut upper = vec![f64::MIN; nb_dim];
+    for point in points.iter() {
+        for (d, &x) in point.iter().enumerate() {
+            lower[d] = lower[d].min(x);
+            upper[d] = upper[d].max(x);
         }
-        points
+    }
+    match metric {
+        Metric::Geodesic => std::f64::consts::PI,
+        _ if points.is_empty() => 0.0,
+        _ => metric.distance(&lower, &upper),
     }
 }
 
-fn _distance_sq(p1: &[f64], p2: &[f64]) -> f64 {
+fn distance_sq(p1: &[f64], p2: &[f64]) -> f64 {
     let mut dist: f64 = 0.0;
     for i in 0..p1.len() {
         dist += (p1[i] - p2[i]) * (p1[i] - p2[i]);
@@ -351,35 +1463,147 @@ fn _distance_sq(p1: &[f64], p2: &[f64]) -> f64 {
     dist
 }
 
+/// Sums the terms of all the coordinates in order, or returns None as soon as the partial sum exceeds
+/// `limit`. The partial sum is checked by chunks of coordinates, to keep the accumulation loop tight.
+fn partial_sum<F: Fn(f64, f64) -> f64>(p1: &[f64], p2: &[f64], limit: f64, term: F) -> Option<f64> {
+    let mut dist: f64 = 0.0;
+    for (c1, c2) in p1.chunks(EARLY_EXIT_CHUNK).zip(p2.chunks(EARLY_EXIT_CHUNK)) {
+        for (&x1, &x2) in c1.iter().zip(c2.iter()) {
+            dist += term(x1, x2);
+        }
+        if dist >= limit {
+            return None;
+        }
+    }
+    Some(dist)
+}
+
 fn manhattan_distance(p1: &[f64], p2: &[f64]) -> f64 {
     p1.iter()
         .zip(p2.iter())
         .fold(0.0, |dist, (d1, d2)| dist + (d1 - d2).abs())
 }
 
-fn wsp_loop_fast(set: &mut PointSet, d_min: f64, mut origin: usize) {
+fn wsp_loop_fast(set: &mut PointSet, d_min: f64, origin: usize, stats: &mut RunStats) {
+    wsp_run(set, |_| d_min, origin, stats);
+}
+
+/// Runs the WSP loop from `origin`. Fixed points are used as origins first, since
+/// the loop does not necessarily visit them otherwise.
+fn wsp_run<F: Fn(usize) -> f64>(
+    set: &mut PointSet,
+    threshold: F,
+    origin: usize,
+    stats: &mut RunStats,
+) {
+    if set.points.is_empty() {
+        return;
+    }
+    let start = Instant::now();
+    if let Some(tree) = set.kdtree.as_mut() {
+        tree.sync(&set.active, &set.visited);
+    }
+    if set.backend == Backend::Grid {
+        let cell_size = grid::cell_size(threshold(origin), set.d_max);
+        if set.grid.as_ref().map(|g| g.min_cell_size()) != Some(cell_size) {
+            let build = Instant::now();
+            set.grid = Some(Grid::new(&set.points, cell_size));
+            set.stats.index_time += build.elapsed().as_secs_f64();
+            set.stats.nb_index_builds += 1;
+        }
+        if let Some(grid) = set.grid.as_mut() {
+            grid.sync(&set.active, &set.visited);
+        }
+    }
+    if set.backend == Backend::Dense && set.idx_sort.is_empty() {
+        set.compute_closest_idx();
+    }
+    let mut run = RunStats {
+        setup_time: start.elapsed().as_secs_f64(),
+        nb_runs: 1,
+        ..Default::default()
+    };
+
+    for i in 0..set.points.len() {
+        if set.fixed[i] && !set.visited[i] {
+            wsp_walk(set, &threshold, i, &mut run);
+        }
+    }
+    wsp_walk(set, threshold, origin, &mut run);
+    set.stats.add_run(&run);
+    *stats += run;
+}
+
+/// Runs the WSP loop corresponding to the backend of the PointSet.
+fn wsp_walk<F: Fn(usize) -> f64>(
+    set: &mut PointSet,
+    threshold: F,
+    origin: usize,
+    stats: &mut RunStats,
+) {
+    let start = Instant::now();
+    match set.backend {
+        Backend::Dense => wsp_loop(set, threshold, origin, stats),
+        Backend::KdTree => kdtree::wsp_loop_tree(set, threshold, origin, 0.0, stats),
+        Backend::Approximate => {
+            kdtree::wsp_loop_tree(set, threshold, origin, kdtree::APPROX_EPSILON, stats)
+        }
+        Backend::Grid => grid::wsp_loop_grid(set, threshold, origin, stats),
+    }
+    stats.loop_time += start.elapsed().as_secs_f64();
+}
+
+/// Core loop of the WSP algorithm. `threshold` gives the minimal distance to
+/// enforce around a given origin. It must only depend on the origin so that the
+/// neighbours of an origin can still be traversed by increasing distance.
+fn wsp_loop<F: Fn(usize) -> f64>(
+    set: &mut PointSet,
+    threshold: F,
+    mut origin: usize,
+    stats: &mut RunStats,
+) {
+    if set.idx_sort.is_empty() {
+        set.compute_closest_idx();
+    }
     loop {
-        let idxs_this_origin = &mut set.idx_sort[origin];
+        let d_min = threshold(origin);
 
         // Iterate over all "active" points closest to the current origin
         // We may iterate over inactive points due to previous loop
         // We stop iterating once we find the next closest point
         // that is 1) active and 2) at a higher distance than *d_min*
         let mut closest_origin = set.idx_active[origin];
-        set.visited[origin] = true;
+        set.visited.set(origin, true);
+        stats.nb_origins += 1;
         loop {
-            if closest_origin >= set.points.len() {
+            if closest_origin >= set.idx_sort[origin].len() {
+                if set.idx_sort[origin].len() < set.points.len() {
+                    // Truncated list of neighbours
+                    set.extend_neighbours(origin);
+                    continue;
+                }
                 return;
             }
-            let point_idx = idxs_this_origin[closest_origin];
+            let point_idx = set.idx_sort[origin][closest_origin];
+            stats.nb_iterations += 1;
             if !set.active[point_idx] {
                 // Not active point
                 closest_origin += 1;
                 continue;
-            } else if set.distance_matrix[origin][point_idx] < d_min {
+            } else if set.fixed[point_idx] && set.is_closer(origin, point_idx, d_min) {
+                // Fixed points are never removed
+                closest_origin += 1;
+            } else if set.is_closer(origin, point_idx, d_min) {
                 // Point too close to the origin => kill
-                set.active[point_idx] = false;
+                set.active.set(point_idx, false);
                 set.nb_active -= 1;
+                stats.nb_removed += 1;
+                if set.observer.is_some() {
+                    let distance = set.distance(origin, point_idx);
+                    if let Some(observer) = set.observer.as_mut() {
+                        observer(origin, point_idx, distance);
+                    }
+                }
                 closest_origin += 1;
             } else if set.visited[point_idx] {
                 closest_origin += 1;
@@ -388,12 +1612,42 @@ fn wsp_loop_fast(set: &mut PointSet, d_min: f64, mut origin: usize) {
                 // Stop the loop and this point is the next origin
                 // Update the closest_origin of the current origin just in case
                 set.idx_active[origin] = closest_origin;
-                origin = idxs_this_origin[closest_origin];
+                origin = point_idx;
                 break; // Further points will always be at a higher distance
             }
         }
     }
 }
+
+/// Resolves the minimal distance, accounting the time spent in the statistics of the run.
+fn resolve_timed(set: &PointSet, d_min: DMin) -> (f64, RunStats) {
+    let start = Instant::now();
+    let d_min = set.resolve_d_min(d_min);
+    let stats = RunStats {
+        setup_time: start.elapsed().as_secs_f64(),
+        ..Default::default()
+    };
+    (d_min, stats)
+}
+
+fn random_origin(set: &PointSet) -> usize {
+    random_origin_with_rng(set, &mut set.rng_algorithm.seeded(set.origin_seed))
+}
+
+fn random_origin_with_rng<R: Rng + ?Sized>(set: &PointSet, rng: &mut R) -> usize {
+    draw_origin(&set.active, rng)
+}
+
+/// Draws a random origin. If the drawn point is not active anymore,
+/// the next active point is used instead. The draw uses 64 bits on all platforms, so a portable
+/// generator gives the same origin on 32-bit targets (e.g. WASM).
+pub(crate) fn draw_origin<R: Rng + ?Sized>(active: &BitSet, rng: &mut R) -> usize {
+    let origin = (rng.next_u64() % active.len().max(1) as u64) as usize;
+    active
+        .next_one(origin)
+        .or_else(|| active.next_one(0))
+        .unwrap_or(origin)
+}
 /// Returns a new vector containing only the active points of the PointSet.
 ///
 /// # Example
@@ -417,11 +1671,15 @@ fn wsp_loop_fast(set: &mut PointSet, d_min: f64, mut origin: usize) {
 /// according to the d_min value of the PointSet structure.
 /// Then, the new origin is the closest valid point from the old origin.
 /// The algorithm iterates like this until all points have been visited or removed.
+/// Returns statistics about the execution, see [`RunStats`].
+///
+/// These inputs are not errors: an empty set is left unchanged, and a single point is always kept.
 ///
 /// # Arguments
 ///
 /// * `set` - The PointSet instance. `set` is mutably borrowed.
 /// * `d_min` - The desired minimal distance between all remaining points in the PointSet.
+///   It is either an absolute distance (`f64`) or a [`DMin`] relative to the scale of the PointSet.
 ///
 /// # Example
 ///
@@ -429,14 +1687,133 @@ fn wsp_loop_fast(set: &mut PointSet, d_min: f64, mut origin: usize) {
 /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
 /// let d_min = 3.0;
 /// wsp::wsp(&mut points, d_min);
+///
+/// // Same, with a minimal distance equal to 30% of the maximal distance between points
+/// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+/// let stats = wsp::wsp(&mut points, wsp::DMin::Relative(0.3));
+/// assert_eq!(stats.nb_removed, 1000 - points.nb_active);
 /// ```
-pub fn wsp(set: &mut PointSet, d_min: f64) {
+pub fn wsp(set: &mut PointSet, d_min: impl Into<DMin>) -> RunStats {
+    let (d_min, mut stats) = resolve_timed(set, d_min.into());
+
     // Step 3: chose random point
-    let mut rng = SmallRng::seed_from_u64(10);
-    let origin: usize = rng.gen::<usize>() % set.points.len();
+    let origin = random_origin(set);
 
     // Step 4, 5, 6: call specific algorithm for speed
-    wsp_loop_fast(set, d_min, origin);
+    wsp_loop_fast(set, d_min, origin, &mut stats);
+    stats
+}
+
+/// Executes the WSP space filling algorithm, drawing the first origin from a caller-supplied
+/// random number generator instead of the fixed internal seed used by [`wsp`].
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The desired minimal distance between all remaining points in the PointSet. See [`wsp`].
+/// * `rng` - The random number generator used to choose the first origin.
+///
+/// # Example
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(51);
+/// let mut points = wsp::PointSet::init_from_random_with_rng(1000, 20, &mut rng);
+/// wsp::wsp_with_rng(&mut points, 3.0, &mut rng);
+/// ```
+pub fn wsp_with_rng<R: Rng + ?Sized>(
+    set: &mut PointSet,
+    d_min: impl Into<DMin>,
+    rng: &mut R,
+) -> RunStats {
+    let (d_min, mut stats) = resolve_timed(set, d_min.into());
+    let origin = random_origin_with_rng(set, rng);
+    wsp_loop_fast(set, d_min, origin, &mut stats);
+    stats
+}
+
+/// Executes the WSP algorithm in sweep mode.
+/// The classical algorithm stops when the chain of origins runs out.
+/// In sweep mode, the algorithm restarts from any remaining active point that has not been
+/// visited yet, until all active points have been used as origins. This guarantees that
+/// all remaining points are at least `d_min` distant from each other.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The desired minimal distance between all remaining points in the PointSet. See [`wsp`].
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+/// wsp::wsp_sweep(&mut points, 3.0);
+/// ```
+pub fn wsp_sweep(set: &mut PointSet, d_min: impl Into<DMin>) -> RunStats {
+    let (d_min, mut stats) = resolve_timed(set, d_min.into());
+
+    let origin = random_origin(set);
+    wsp_loop_fast(set, d_min, origin, &mut stats);
+
+    // Visited points are never visited again, so a single pass is enough
+    let mut next = set.active.next_one_and_not(&set.visited, 0);
+    while let Some(i) = next {
+        wsp_walk(set, |_| d_min, i, &mut stats);
+        next = set.active.next_one_and_not(&set.visited, i + 1);
+    }
+    stats
+}
+
+/// Executes the WSP algorithm with a non-uniform target density.
+/// The minimal distance enforced around each origin is scaled according to
+/// the relative density requested at this origin: regions with a density higher than 1
+/// keep more points, and regions with a density lower than 1 keep fewer points.
+///
+/// For a point `x` of dimension `m`, the effective minimal distance is
+/// `d_min / density(x)^(1/m)`, such that the number of points per unit of volume
+/// follows (approximately) the density.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The minimal distance between points where the density equals 1. See [`wsp`].
+/// * `density` - The relative density of points. It must return strictly positive values.
+///
+/// # Example
+///
+/// The following code snippet keeps more points close to the origin of the space.
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+/// let density = |p: &[f64]| 1.0 / (0.1 + p.iter().sum::<f64>());
+/// wsp::wsp_weighted(&mut points, 0.1, &density);
+/// ```
+pub fn wsp_weighted(
+    set: &mut PointSet,
+    d_min: impl Into<DMin>,
+    density: &dyn Fn(&[f64]) -> f64,
+) -> RunStats {
+    let (d_min, mut stats) = resolve_timed(set, d_min.into());
+    let start = Instant::now();
+    let thresholds: Vec<f64> = set
+        .points
+        .iter()
+        .map(|point| {
+            let weight = density(point);
+            assert!(
+                weight > 0.0 && weight.is_finite(),
+                "The density must be strictly positive and finite, got {}",
+                weight
+            );
+            d_min / weight.powf(1.0 / point.len() as f64)
+        })
+        .collect();
+
+    stats.setup_time += start.elapsed().as_secs_f64();
+
+    let origin = random_origin(set);
+    wsp_run(set, |i| thresholds[i], origin, &mut stats);
+    stats
 }
 
 /// This is an adaptive version of the WSP algorithm.
@@ -444,6 +1821,13 @@ pub fn wsp(set: &mut PointSet, d_min: f64) {
 /// based on that we obtain a set of a given number of points.
 /// Here we adaptively change d_min to get (an approximation of)
 /// the desired number of points active after the algorithm.
+/// The returned statistics are accumulated over all the iterations.
+///
+/// An empty set or a single point is left unchanged, without running the algorithm.
+/// Since the algorithm keeps at least one point, a target of 0 is treated as 1. If the target is not
+/// below the number of active points (e.g. a target larger than the pool), the set is left unchanged.
+/// If all the distances are equal (e.g. identical points), the set either keeps all its points or
+/// a single one, whichever is closer to the target.
 ///
 /// # Arguments
 ///
@@ -458,60 +1842,161 @@ pub fn wsp(set: &mut PointSet, d_min: f64) {
 /// let objective_nb: usize = 100;
 /// wsp::adaptive_wsp(&mut points, objective_nb, false);
 /// ```
-pub fn adaptive_wsp(set: &mut PointSet, obj_nb: usize, verbose: bool) {
-    let mut d_min = set.d_min;
-    let mut d_max = set.d_max;
-    let mut d_search = (d_min + d_max) / 2.0;
-    let mut iter = 0;
-    let mut best_distance = 0.0;
-    let mut best_difference_active = set.nb_active - obj_nb;
+pub fn adaptive_wsp(set: &mut PointSet, obj_nb: usize, verbose: bool) -> RunStats {
+    adaptive_wsp_with_selection(set, obj_nb, verbose, SelectionCriterion::default())
+}
+
+/// Same as [`adaptive_wsp`], choosing with `criterion` between the iterations whose number of active points
+/// is equally close to the target. With the default criterion, [`SelectionCriterion::MaxActive`], the first
+/// of these iterations is kept, as in [`adaptive_wsp`].
+///
+/// # Example
+///
+/// ```
+/// use wsp::SelectionCriterion;
+///
+/// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+/// wsp::adaptive_wsp_with_selection(&mut points, 100, false, SelectionCriterion::MinPhiP(50.0));
+/// ```
+pub fn adaptive_wsp_with_selection(
+    set: &mut PointSet,
+    obj_nb: usize,
+    verbose: bool,
+    criterion: SelectionCriterion,
+) -> RunStats {
+    let mut search = AdaptiveSearch::new(set, obj_nb, criterion);
+    match adaptive_loop(set, &mut search, verbose, |_, _| Ok(())) {
+        Ok(()) => search.stats,
+        Err(_) => unreachable!("The adaptive algorithm only fails when writing checkpoints"),
+    }
+}
+
+/// State of the binary search of the adaptive algorithm, between two iterations.
+struct AdaptiveSearch {
+    obj_nb: usize,
+    d_min: f64,
+    d_max: f64,
+    d_search: f64,
+    iter: usize,
+    best_distance: f64,
+    best_difference_active: usize,
+    /// Best design found so far, restored at the end instead of being recomputed
+    best_design: Option<Snapshot>,
+    criterion: SelectionCriterion,
+    best_score: f64,
+    stats: RunStats,
+}
+
+impl AdaptiveSearch {
+    fn new(set: &PointSet, obj_nb: usize, criterion: SelectionCriterion) -> AdaptiveSearch {
+        AdaptiveSearch {
+            obj_nb,
+            d_min: set.d_min,
+            d_max: set.d_max,
+            d_search: (set.d_min + set.d_max) / 2.0,
+            iter: 0,
+            best_distance: 0.0,
+            best_difference_active: set.nb_active.abs_diff(obj_nb.max(1)),
+            best_design: None,
+            criterion,
+            best_score: f64::NEG_INFINITY,
+            stats: RunStats::default(),
+        }
+    }
+}
+
+/// Core of the adaptive algorithm. `checkpoint` is called before each new iteration,
+/// with the PointSet reset and the state of the search, so that the search can be resumed later.
+fn adaptive_loop<F>(
+    set: &mut PointSet,
+    search: &mut AdaptiveSearch,
+    verbose: bool,
+    mut checkpoint: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(&mut PointSet, &AdaptiveSearch) -> Result<(), Box<dyn Error>>,
+{
+    // WSP keeps at least one point
+    let obj_nb = search.obj_nb.max(1);
+    if search.iter == 0 && obj_nb >= set.nb_active {
+        // No distance removes points without moving away from the target
+        return Ok(());
+    }
+    if search.iter == 0 && search.d_max <= search.d_min {
+        // All the distances are equal (e.g. identical points): any larger distance only keeps a single
+        // point (and the fixed points). Keep the design closest to the target
+        let initial = set.snapshot();
+        search.iter += 1;
+        search.stats += wsp(set, search.d_max.next_up());
+        if set.nb_active.abs_diff(obj_nb) > initial.nb_active().abs_diff(obj_nb) {
+            set.restore(&initial);
+        }
+        return Ok(());
+    }
     loop {
-        iter += 1;
-        wsp(set, d_search);
+        search.iter += 1;
+        search.stats += wsp(set, search.d_search);
 
         // Binary search the best d_min
         if verbose {
             println!(
                 "Iter #{}: distance={}, nb_active={}",
-                iter, d_search, set.nb_active
+                search.iter, search.d_search, set.nb_active
             );
         }
-        match set.nb_active.cmp(&obj_nb) {
-            Ordering::Greater => d_min = d_search,
-            Ordering::Less => d_max = d_search,
-            Ordering::Equal => return,
-        };
-
         // The search space is not continuous.
-        // We must also track the best result to recover it afterwards
-        if (set.nb_active as i32 - obj_nb as i32).abs() < best_difference_active as i32 {
-            best_difference_active = (set.nb_active as i32 - obj_nb as i32).abs() as usize;
-            best_distance = d_search;
+        // We must also track the best result to recover it afterwards. With the default criterion, the
+        // first iteration closest to the target is kept; other criteria break the ties
+        let difference_active = set.nb_active.abs_diff(obj_nb);
+        let tie_break = !matches!(search.criterion, SelectionCriterion::MaxActive)
+            && difference_active == search.best_difference_active;
+        if difference_active < search.best_difference_active || tie_break {
+            let score = search.criterion.score(set);
+            if !tie_break || score > search.best_score {
+                search.best_difference_active = difference_active;
+                search.best_distance = search.d_search;
+                search.best_design = Some(set.snapshot());
+                search.best_score = score;
+            }
         }
 
+        match set.nb_active.cmp(&obj_nb) {
+            Ordering::Greater => search.d_min = search.d_search,
+            Ordering::Less => search.d_max = search.d_search,
+            Ordering::Equal => return Ok(()),
+        };
+
         // Stop condition if we cannot exactly reach the target number
-        let last_d_search = d_search;
-        d_search = (d_min + d_max) / 2.0;
-        if (last_d_search - d_search).abs() <= f64::EPSILON {
+        let last_d_search = search.d_search;
+        search.d_search = (search.d_min + search.d_max) / 2.0;
+        if (last_d_search - search.d_search).abs() <= f64::EPSILON {
             break;
         }
 
-        // Reset parameters for the next iteration
+        // Reset parameters for the next iteration. WSP is not monotone in the distance: starting
+        // from the current active points would not give the design of the larger distance
         set.reset_reseach_params();
+        checkpoint(set, search)?;
     }
 
-    // Recompute a last time if the best distance is not the last computed distance
-    if (best_distance - d_search).abs() > f64::EPSILON {
-        d_search = best_distance;
-        set.reset_reseach_params();
-        wsp(set, d_search);
+    // Go back to the best design if it is not the last computed one
+    if (search.best_distance - search.d_search).abs() > f64::EPSILON {
+        search.d_search = search.best_distance;
+        match search.best_design.as_ref() {
+            Some(best) => set.restore(best),
+            None => {
+                set.reset_reseach_params();
+                search.stats += wsp(set, search.d_search);
+            }
+        }
     }
     if verbose {
         println!(
             "Last iter: best approximation is distance={}, nb_active={}",
-            d_search, set.nb_active
+            search.d_search, set.nb_active
         );
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -521,11 +2006,11 @@ mod tests {
     fn test_distance_sq() {
         let mut p1: Vec<f64> = vec![1.0, 0.0];
         let mut p2 = vec![0.0, 0.0];
-        assert_eq!(_distance_sq(&p1, &p2), 1.0);
+        assert_eq!(distance_sq(&p1, &p2), 1.0);
 
         p1 = vec![2.0, 2.0];
         p2 = vec![2.0, 9.0];
-        assert_eq!(_distance_sq(&p1, &p2), 49.0);
+        assert_eq!(distance_sq(&p1, &p2), 49.0);
     }
 
     #[test]
@@ -540,12 +2025,13 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec, clippy::needless_range_loop)]
     fn test_distance_matrix() {
         let p1 = vec![0.0, 0.0];
         let p2 = vec![4.0, 0.0];
         let p3 = vec![4.0, 3.0];
         let (distance_matrix, d_min, d_max) =
-            PointSet::compute_distance_matrix(&vec![p1, p2, p3], Some(&_distance_sq));
+            PointSet::compute_distance_matrix(&vec![p1, p2, p3], Some(&distance_sq));
 
         let true_distance = vec![
             vec![0.0, 16.0, 25.0],
@@ -564,12 +2050,52 @@ mod tests {
     }
 
     #[test]
+    fn test_distance_below() {
+        let points = PointSet::init_from_random(50, 37, 51).points;
+        for metric in [Metric::Manhattan, Metric::Euclidean, Metric::Geodesic] {
+            for p1 in points.iter() {
+                for p2 in points.iter() {
+                    let d = metric.distance(p1, p2);
+                    for limit in [0.0, d, 0.5 * d, 2.0 * d, d + 1e-15, f64::INFINITY] {
+                        assert_eq!(
+                            metric.distance_below(p1, p2, limit, Summation::Naive),
+                            Some(d).filter(|&d| d < limit)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiled_distance_matrix() {
+        // Several tiles, the last one being partial
+        let points = PointSet::init_from_random(600, 2, 51).points;
+        let (distance_matrix, d_min, d_max) = PointSet::compute_distance_matrix(&points, None);
+        let (mut true_min, mut true_max) = (f64::MAX, 0.0f64);
+        for i in 0..600 {
+            assert_eq!(distance_matrix[i][i], 0.0);
+            for j in 0..600 {
+                if i != j {
+                    let d = manhattan_distance(&points[i], &points[j]);
+                    assert_eq!(distance_matrix[i][j], d);
+                    true_min = true_min.min(d);
+                    true_max = true_max.max(d);
+                }
+            }
+        }
+        assert_eq!((d_min, d_max), (true_min, true_max));
+    }
+
+    #[test]
+    #[allow(clippy::useless_vec, clippy::needless_range_loop)]
     fn test_closest_idx() {
         let p1 = vec![0.0, 0.0];
         let p2 = vec![1.0, 0.1];
         let p3 = vec![1.0, 1.0];
         let p4 = vec![2.0, 1.0];
-        let pointset = PointSet::init_from_preset(vec![p1, p2, p3, p4]);
+        let mut pointset = PointSet::init_from_preset(vec![p1, p2, p3, p4]);
+        pointset.compute_closest_idx();
 
         let true_idxs = vec![
             vec![0, 1, 2, 3],
@@ -586,6 +2112,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_iterative_fast_1() {
         let p1 = vec![0.0, 0.0];
         let p2 = vec![1.0, 0.1];
@@ -593,7 +2120,7 @@ mod tests {
         let p4 = vec![2.0, 1.0];
         let mut pointset = PointSet::init_from_preset(vec![p1, p2, p3, p4]);
 
-        wsp_loop_fast(&mut pointset, 1.0, 1);
+        wsp_loop_fast(&mut pointset, 1.0, 1, &mut RunStats::default());
 
         // The expected behaviour is
         // 1) * p3 too close => becomes inactive
@@ -639,4 +2166,214 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_weighted_density() {
+        let d_min: f64 = 0.1;
+        let mut points = PointSet::init_from_random(1000, 2, 51);
+        // Four times denser in the left half of the space
+        let density = |p: &[f64]| if p[0] < 0.5 { 4.0 } else { 1.0 };
+        wsp_weighted(&mut points, d_min, &density);
+
+        let nb_left = points.get_remaining().iter().filter(|p| p[0] < 0.5).count();
+        assert!(nb_left > points.nb_active - nb_left);
+
+        // Points on the right are still at least d_min distant from each other
+        for i in 0..1000 {
+            for j in i + 1..1000 {
+                if points.active[i]
+                    && points.active[j]
+                    && points.points[i][0] >= 0.5
+                    && points.points[j][0] >= 0.5
+                {
+                    assert!(points.distance_matrix[i][j] >= d_min);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sweep_restarts() {
+        let d_min: f64 = 0.04;
+        let mut points = PointSet::init_from_random(1000, 3, 51);
+        // Pretend that some points were already visited by a previous walk
+        // whose origins did not clear the space around them
+        for i in 0..500 {
+            points.visited.set(i, true);
+        }
+        wsp_sweep(&mut points, d_min);
+
+        // All other active points were origins and cleared the space around them
+        for j in 500..1000 {
+            if !points.active[j] {
+                continue;
+            }
+            for i in 0..1000 {
+                if i != j && points.active[i] {
+                    assert!(points.distance_matrix[i][j] >= d_min);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncated_neighbours() {
+        let mut full = PointSet::init_from_random(1000, 3, 51);
+        let mut truncated = PointSet::init_from_random(1000, 3, 51).with_nb_neighbours(4);
+        wsp(&mut full, 0.04);
+        wsp(&mut truncated, 0.04);
+
+        assert_eq!(full.active, truncated.active);
+        assert!(truncated.idx_sort.iter().any(|idxs| idxs.len() == 5));
+        // Only the last origin needs its full list of neighbours
+        let nb_full = truncated
+            .idx_sort
+            .iter()
+            .filter(|idxs| idxs.len() == 1000)
+            .count();
+        assert_eq!(nb_full, 1);
+    }
+
+    #[test]
+    fn test_caller_supplied_rng() {
+        let mut rng = SmallRng::seed_from_u64(51);
+        let mut points = PointSet::init_from_random_with_rng(500, 3, &mut rng);
+        assert_eq!(points.points, PointSet::init_from_random(500, 3, 51).points);
+
+        // Same origin as the internal seed of wsp()
+        let mut reference = PointSet::init_from_random(500, 3, 51);
+        wsp(&mut reference, 0.1);
+        wsp_with_rng(&mut points, 0.1, &mut SmallRng::seed_from_u64(10));
+        assert_eq!(points.active, reference.active);
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PointSet>();
+    }
+
+    #[test]
+    fn test_removal_observer() {
+        use std::sync::{Arc, Mutex};
+
+        for backend in [Backend::Dense, Backend::KdTree] {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let log = Arc::clone(&events);
+            let mut points = PointSet::init_from_random(500, 3, 51).with_backend(backend);
+            points.set_observer(move |origin, victim, d| {
+                log.lock().unwrap().push((origin, victim, d))
+            });
+            wsp(&mut points, 0.2);
+
+            let events = events.lock().unwrap();
+            assert_eq!(events.len(), 500 - points.nb_active);
+            for &(origin, victim, d) in events.iter() {
+                assert!(points.active[origin] && !points.active[victim]);
+                assert!(d < 0.2);
+                assert_eq!(d, points.distance(origin, victim));
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_origin_is_active() {
+        let mut points = PointSet::init_from_random(100, 2, 51);
+        let origin = random_origin(&points);
+        points.active.set(origin, false);
+        points.active.set((origin + 1) % 100, false);
+        assert_eq!(random_origin(&points), (origin + 2) % 100);
+    }
+
+    #[test]
+    fn test_random_origin_is_portable() {
+        let points = PointSet::init_from_random(1000, 2, 51);
+        for (algorithm, expected) in [(RngAlgorithm::ChaCha20, 810), (RngAlgorithm::Pcg64, 330)] {
+            let mut rng = algorithm.seeded(derive_seed(51, SeedStream::Origin));
+            assert_eq!(random_origin_with_rng(&points, &mut rng), expected);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_keeps_best_iteration() {
+        for seed in 0..10 {
+            let mut points = PointSet::init_from_random(800, 3, seed);
+            let mut search = AdaptiveSearch::new(&points, 100, SelectionCriterion::default());
+            adaptive_loop(&mut points, &mut search, false, |_, _| Ok(())).unwrap();
+            assert_eq!(
+                points.nb_active.abs_diff(100),
+                search.best_difference_active
+            );
+            assert_eq!(points.nb_active, points.active.count_ones());
+        }
+    }
+
+    #[test]
+    fn test_adaptive_default_keeps_first_closest_iteration() {
+        for seed in 0..10 {
+            let mut points = PointSet::init_from_random(800, 3, seed);
+            let mut search = AdaptiveSearch::new(&points, 100, SelectionCriterion::default());
+            let mut best = (search.best_difference_active, search.best_distance);
+            adaptive_loop(&mut points, &mut search, false, |_, search| {
+                // The best distance only changes on a strictly closer iteration
+                if search.best_distance != best.1 {
+                    assert!(search.best_difference_active < best.0);
+                }
+                best = (search.best_difference_active, search.best_distance);
+                Ok(())
+            })
+            .unwrap();
+            if search.best_distance != best.1 {
+                assert!(search.best_difference_active < best.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tiny_candidate_sets() {
+        let pool = PointSet::init_from_random(50, 2, 51).points;
+        for backend in [
+            Backend::Dense,
+            Backend::KdTree,
+            Backend::Approximate,
+            Backend::Grid,
+        ] {
+            let init = |points: Vec<Vec<f64>>| {
+                PointSet::init_from_preset_with_backend(points, Metric::Manhattan, backend)
+            };
+
+            let mut empty = init(Vec::new());
+            wsp(&mut empty, 0.1);
+            adaptive_wsp(&mut empty, 3, false);
+            assert_eq!(empty.nb_active, 0);
+
+            let mut single = init(vec![vec![0.5, 0.5]]);
+            let stats = adaptive_wsp(&mut single, 0, false);
+            assert_eq!((single.nb_active, stats.nb_runs), (1, 0));
+            wsp(&mut single, 0.1);
+            assert_eq!(single.nb_active, 1);
+
+            // Identical points: a single point is closer to the target than the 10 points
+            let mut identical = init(vec![vec![0.5, 0.5]; 10]);
+            adaptive_wsp(&mut identical, 3, false);
+            assert_eq!(identical.nb_active, 1);
+            let mut identical = init(vec![vec![0.5, 0.5]; 10]);
+            adaptive_wsp(&mut identical, 8, false);
+            assert_eq!(identical.nb_active, 10);
+
+            // Target larger than the pool
+            let mut larger = init(pool.clone());
+            let stats = adaptive_wsp(&mut larger, 80, false);
+            assert_eq!((larger.nb_active, stats.nb_runs), (50, 0));
+
+            let mut none = init(pool.clone());
+            adaptive_wsp(&mut none, 0, false);
+            assert_eq!(none.nb_active, 1);
+
+            // Minimal distance larger than the maximal distance
+            let mut far = init(pool.clone());
+            wsp(&mut far, 1e9);
+            assert_eq!(far.nb_active, 1);
+        }
+    }
 }
@@ -1,64 +1,718 @@
+use std::env;
+use std::error::Error;
+use std::path::Path;
 use std::process;
+use std::str::FromStr;
+use std::time::Instant;
+use structopt::clap::Shell;
 use structopt::StructOpt;
-use wsp::{adaptive_wsp, wsp, PointSet};
+use wsp::{
+    adaptive_wsp_with_checkpoint, adaptive_wsp_with_selection, cluster_wsp, derive_seed, latinize,
+    min_distance, read_points_binary, read_points_csv, resume_adaptive_wsp, sensitivity,
+    subsample_with_rng, uniform_candidates, write_points_binary, write_points_csv, wsp,
+    wsp_annealed, wsp_restarts, wsp_sweep, Backend, CsvChunks, DMin, Manifest, Metric,
+    MissingValues, Normalization, OutputScale, PointSet, Quantization, RngAlgorithm, RunStats,
+    SeedStream, SelectionCriterion, StreamingWsp, Subsampling, Summation, MAX_FRACTIONAL_BITS,
+};
 
 /// Set the parameters of the WSP space filling algorithm
-#[derive(StructOpt)]
+#[derive(StructOpt, Debug)]
 struct Cli {
-    /// Output file where the matrix is stored after WSP
+    /// Output file where the matrix is stored after WSP. Stored in the raw binary format if the name ends with ".bin", in CSV otherwise
     #[structopt(short = "o", long = "output", default_value = "wsp.csv")]
     output_file: String,
-    /// Output file where the matrix is stored before WSP
+    /// Output file where the matrix is stored before WSP. Same format rules as --output
     #[structopt(short = "i", long = "initial")]
     output_file_before: Option<String>,
-    /// Algorithm to generate the initial set of candidate points (low impact)
+    /// Store the initial candidates, the design, the active mask and the representatives of the removed candidates
+    /// in CSV files sharing the prefix <export>, with consistent indexes. Replaces --output, --initial and --representatives
+    #[structopt(long = "export")]
+    export_prefix: Option<String>,
+    /// Read the initial set of candidate points from a file instead of generating it. Read in the raw binary format if the name ends with ".bin", in CSV otherwise
+    #[structopt(long = "input")]
+    input_file: Option<String>,
+    /// Normalize each dimension of the input points onto [0, 1] before WSP. The minimal distance is then expressed in normalized units
+    #[structopt(long = "normalize")]
+    normalize: bool,
+    /// Reduce a huge input pool before WSP: "uniform:<nb-points>" keeps random candidates, "grid:<cell-size>" keeps one candidate per grid cell. Requires --input
+    #[structopt(long = "subsample")]
+    subsampling: Option<Subsampling>,
+    /// Coordinates of the output points with --normalize: "normalized" or "original" units. The transform is stored in <output>.transform.csv
+    #[structopt(long = "output-scale", default_value = "original")]
+    output_scale: OutputScale,
+    /// Algorithm to generate the initial set of candidate points (low impact): "random", "simplex" or "sphere"
     #[structopt(short = "a", long = "algo", default_value = "random")]
-    _initial_algo: String,
+    initial_algo: String,
     /// Number of points in the initial set of candidate points (major impact)
     #[structopt(short = "n", long = "nb-initial", default_value = "2000")]
     nb_initial: usize,
     /// Minimal distance desired
     #[structopt(short = "d", long = "distance", default_value = "1.0")]
     d_min: f64,
+    /// Interpret the minimal distance as a fraction of the "max" or "mean" distance between the initial points
+    #[structopt(long = "relative")]
+    relative: Option<String>,
+    /// Restart from unvisited points until all active points are visited
+    #[structopt(long = "sweep")]
+    sweep: bool,
+    /// Decrease the minimal distance over <nb-passes> passes, from coarse to fine, ending at the minimal distance
+    #[structopt(long = "anneal")]
+    nb_passes: Option<usize>,
+    /// Ratio between the minimal distances of two consecutive passes. Only with --anneal
+    #[structopt(long = "cooling", default_value = "0.5")]
+    cooling: f64,
+    /// Run the classic algorithm from <nb-restarts> different origins and keep the best design (see --select)
+    #[structopt(long = "restarts")]
+    nb_restarts: Option<usize>,
+    /// Definition of the best design of --restarts, and between the adaptive iterations equally close to the target:
+    /// "max-active" (default), "max-min-distance" or "min-phi:<p>" (Morris-Mitchell criterion)
+    #[structopt(long = "select")]
+    selection: Option<SelectionCriterion>,
+    /// Move the points of the design so that their projection on each dimension is evenly stratified,
+    /// as long as their minimal distance does not decrease. Not for --algo simplex or sphere
+    #[structopt(long = "latinize")]
+    latinize: bool,
+    /// Test the candidates one at a time against the design instead of storing the pool: the -n random candidates,
+    /// or the points of the CSV --input. Keeps a candidate if it is at least the minimal distance away from the kept points
+    #[structopt(long = "stream")]
+    stream: bool,
     /// Dimension of the points
     #[structopt(short = "m", long = "dimension", default_value = "20")]
     dim: usize,
-    /// Seed for the origin choice and the initialization
+    /// Seed of the run. The seeds of the generation, of the origin choice, of the clustering and of the sub-sampling are derived from it
     #[structopt(short = "s", long = "seed", default_value = "51")]
     seed: u64,
+    /// Algorithm of the random number generators: "small" (fastest, but not portable), "chacha20" or "pcg64" (documented and portable)
+    #[structopt(long = "rng", default_value = "small")]
+    rng: RngAlgorithm,
     /// Use adaptive algorithm instead of distance input to reach <nb-target> active points in the space
     #[structopt(long = "adaptive")]
     nb_target: Option<usize>,
-    /// Display debug information. Only for adaptive WSP
+    /// Run the adaptive algorithm independently in <nb-clusters> k-means clusters. Requires --adaptive
+    #[structopt(long = "clusters")]
+    nb_clusters: Option<usize>,
+    /// Display debug information: the number of remaining points, the run statistics and the phase timings of any mode, and the iterations of the adaptive algorithm
     #[structopt(short = "v", long = "verbose")]
     verbose: bool,
+    /// Algorithm used to find the neighbours: "dense", "kdtree", "approximate" or "grid". Chosen from the problem size by default
+    #[structopt(short = "b", long = "backend")]
+    backend: Option<Backend>,
+    /// Summation of the per-dimension terms of the distances: "naive" or "neumaier" (compensated, more accurate in high dimensions)
+    #[structopt(long = "summation")]
+    summation: Option<Summation>,
+    /// Store the distance matrix with "u16" or "u8" codes instead of 64-bit floats, dividing its memory by 4 or 8. Only for the dense backend
+    #[structopt(long = "quantize")]
+    quantization: Option<Quantization>,
+    /// Snap the coordinates to multiples of 2^-<bits> so that the distances are exact, and the design identical on all platforms. Not for the geodesic distance
+    #[structopt(long = "fixed-point")]
+    fixed_point: Option<u32>,
+    /// Only keep the <nb-neighbours> closest neighbours of each point in memory. Only for the dense backend
+    #[structopt(short = "k", long = "neighbours")]
+    nb_neighbours: Option<usize>,
+    /// Store the initial points, distances and sorted neighbours in a binary file, to reuse them with --load-index
+    #[structopt(long = "save-index")]
+    save_index: Option<String>,
+    /// Load the initial points, distances and sorted neighbours from a file written with --save-index
+    #[structopt(long = "load-index")]
+    load_index: Option<String>,
+    /// Map the output points from the unit cube onto the given ranges, e.g. "0:10,-5:5" (use --bounds=-5:5 if the first bound is negative)
+    #[structopt(long = "bounds")]
+    bounds: Option<Bounds>,
+    /// Store the state of the adaptive algorithm in a checkpoint file after each iteration, to resume it with --resume
+    #[structopt(long = "checkpoint")]
+    checkpoint: Option<String>,
+    /// Resume an adaptive run from a checkpoint file written with --checkpoint, with the seed and the generator of the original run. Other input options are ignored
+    #[structopt(long = "resume")]
+    resume: Option<String>,
+    /// Report how many points enter and leave the design when the minimal distance is decreased or increased by <delta>. Only for the classic algorithm with an absolute distance
+    #[structopt(long = "sensitivity")]
+    sensitivity: Option<f64>,
+    /// Store statistics about the execution of the algorithm in a CSV file
+    #[structopt(long = "stats")]
+    stats_file: Option<String>,
+    /// Store, for each removed candidate, the index of its closest remaining point in a CSV file
+    #[structopt(long = "representatives")]
+    representatives_file: Option<String>,
     /// Transport the output matrix. Initially the matrix is nb points * nb dims.
     #[structopt(short = "t", long = "transpose")]
     transpose: bool,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands other than running WSP
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Print the completion script of the command line for a shell: "bash", "zsh", "fish", "powershell" or "elvish"
+    Completions {
+        #[structopt(possible_values = &Shell::variants())]
+        shell: Shell,
+    },
 }
 
 fn main() {
-    let args = Cli::from_args();
+    let matches = Cli::clap().get_matches();
+    let args = Cli::from_clap(&matches);
+    if let Some(Command::Completions { shell }) = args.command {
+        Cli::clap().gen_completions_to("wsp", shell, &mut std::io::stdout());
+        return;
+    }
+    let manifest = input_manifest(&args);
+
+    if let Some(filename) = args.resume.as_deref() {
+        if matches.occurrences_of("seed") > 0 || matches.occurrences_of("rng") > 0 {
+            eprintln!("A resumed run keeps the seed and the generator of the checkpoint, without --seed or --rng");
+            process::exit(1);
+        }
+        match resume_adaptive_wsp(filename, args.verbose) {
+            Ok((points, stats)) => save_results(&args, &points, &stats, manifest),
+            Err(err) => {
+                eprintln!("Error resuming from the checkpoint: {}", err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+    if args.checkpoint.is_some() && (args.nb_target.is_none() || args.nb_clusters.is_some()) {
+        eprintln!("The --checkpoint option requires --adaptive, without --clusters");
+        process::exit(1);
+    }
+    if args.export_prefix.is_some()
+        && (args.output_file_before.is_some() || args.representatives_file.is_some())
+    {
+        eprintln!("The --export option replaces --initial and --representatives");
+        process::exit(1);
+    }
+    if args.sensitivity.is_some()
+        && (args.nb_target.is_some()
+            || args.relative.is_some()
+            || args.sweep
+            || args.nb_passes.is_some())
+    {
+        eprintln!(
+            "The --sensitivity option requires the classic algorithm with an absolute distance"
+        );
+        process::exit(1);
+    }
+    if args.sensitivity.is_some_and(|delta| delta < 0.0) {
+        eprintln!("The perturbation of --sensitivity must be positive");
+        process::exit(1);
+    }
+    if args.nb_restarts.is_some()
+        && (args.nb_target.is_some()
+            || args.sweep
+            || args.nb_passes.is_some()
+            || args.sensitivity.is_some())
+    {
+        eprintln!("The --restarts option requires the classic algorithm, without --sensitivity");
+        process::exit(1);
+    }
+    if args.nb_restarts == Some(0) {
+        eprintln!("The number of restarts must be positive");
+        process::exit(1);
+    }
+    if args.selection.is_some()
+        && args.nb_restarts.is_none()
+        && (args.nb_target.is_none() || args.checkpoint.is_some() || args.nb_clusters.is_some())
+    {
+        eprintln!("The --select option requires --restarts or --adaptive, without --checkpoint or --clusters");
+        process::exit(1);
+    }
+    if args
+        .fixed_point
+        .is_some_and(|bits| bits > MAX_FRACTIONAL_BITS)
+    {
+        eprintln!(
+            "The --fixed-point option takes at most {} bits",
+            MAX_FRACTIONAL_BITS
+        );
+        process::exit(1);
+    }
+    if args.latinize && args.initial_algo != "random" {
+        eprintln!("The --latinize option only applies to designs in a box");
+        process::exit(1);
+    }
+    if (args.normalize || args.subsampling.is_some()) && args.input_file.is_none() {
+        eprintln!("The --normalize and --subsample options require --input");
+        process::exit(1);
+    }
+    if args.stream {
+        if args.nb_target.is_some()
+            || args.relative.is_some()
+            || args.sweep
+            || args.nb_passes.is_some()
+            || args.nb_restarts.is_some()
+            || args.sensitivity.is_some()
+            || args.normalize
+            || args.subsampling.is_some()
+            || args.load_index.is_some()
+            || args.save_index.is_some()
+            || args.fixed_point.is_some()
+            || args.output_file_before.is_some()
+            || args.export_prefix.is_some()
+            || args.initial_algo != "random"
+            || args
+                .input_file
+                .as_deref()
+                .is_some_and(|f| f.ends_with(".bin"))
+        {
+            eprintln!("The --stream option requires the classic algorithm with an absolute distance, on random or CSV candidates, without options using the whole pool");
+            process::exit(1);
+        }
+        match stream_design(&args) {
+            Ok((points, stats)) => save_results(&args, &points, &stats, manifest),
+            Err(err) => {
+                eprintln!("Error streaming the candidates: {}", err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut rng = args
+        .rng
+        .seeded(derive_seed(args.seed, SeedStream::Generation));
+    let mut points: PointSet = match (args.load_index.as_deref(), args.initial_algo.as_str()) {
+        (Some(filename), _) => match PointSet::load_index(filename) {
+            Ok(points) => points,
+            Err(err) => {
+                eprintln!("Error loading the index: {}", err);
+                process::exit(1);
+            }
+        },
+        (None, _) if args.input_file.is_some() => {
+            match read_points(args.input_file.as_deref().unwrap())
+                .map(|points| subsample_points(points, &args))
+            {
+                Ok(points) if args.normalize => match PointSet::init_from_preset_normalized(points)
+                {
+                    Ok(points) => points,
+                    Err(err) => {
+                        eprintln!("Error reading the input: {}", err);
+                        process::exit(1);
+                    }
+                },
+                Ok(points) => match PointSet::init_from_preset_with_missing(
+                    points,
+                    Metric::Manhattan,
+                    MissingValues::Reject,
+                ) {
+                    Ok(points) => points,
+                    Err(err) => {
+                        eprintln!("Error reading the input: {}", err);
+                        process::exit(1);
+                    }
+                },
+                Err(err) => {
+                    eprintln!("Error reading the input: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        (None, "random") => {
+            PointSet::init_from_random_with_rng(args.nb_initial, args.dim, &mut rng)
+        }
+        (None, "simplex") => {
+            PointSet::init_from_random_simplex_with_rng(args.nb_initial, args.dim, &mut rng)
+        }
+        (None, "sphere") => {
+            PointSet::init_from_random_sphere_with_rng(args.nb_initial, args.dim, &mut rng)
+        }
+        (None, algo) => {
+            eprintln!("Unknown initial algorithm: {}", algo);
+            process::exit(1);
+        }
+    };
+
+    check_bounds(&args, &points);
+
+    // Before the settings rebuilding the PointSet, so that the dense backend only computes quantized distances
+    if let Some(quantization) = args.quantization {
+        points = points.with_quantization(quantization);
+    }
+    if let Some(bits) = args.fixed_point {
+        points = points.with_fixed_point(bits);
+    }
+    if let Some(backend) = args.backend {
+        points = points.with_backend(backend);
+    }
+    points = points.with_rng_algorithm(args.rng).with_seed(args.seed);
+    if let Some(summation) = args.summation {
+        points = points.with_summation(summation);
+    }
+    if let Some(k) = args.nb_neighbours {
+        points = points.with_nb_neighbours(k);
+    }
+
+    if let Some(filename) = args.save_index.as_deref() {
+        if let Err(err) = points.save_index(filename) {
+            eprintln!("Error writing the index: {}", err);
+            process::exit(1);
+        }
+    }
+
+    if let Some(filename) = args.output_file_before.as_deref() {
+        let initial: Vec<&Vec<f64>> = points.iter_remaining().collect();
+        if let Err(err) = save_points(&points, &initial, filename, &args) {
+            eprintln!("Error writing the points: {}", err);
+            process::exit(1);
+        }
+    }
+
+    let stats = match (args.nb_target, args.nb_clusters) {
+        (Some(obj_nb), Some(k)) => cluster_wsp(
+            &mut points,
+            k,
+            obj_nb,
+            derive_seed(args.seed, SeedStream::Clustering),
+        ),
+        (Some(obj_nb), None) => match args.checkpoint.as_deref() {
+            Some(filename) => {
+                match adaptive_wsp_with_checkpoint(&mut points, obj_nb, args.verbose, filename) {
+                    Ok(stats) => stats,
+                    Err(err) => {
+                        eprintln!("Error writing the checkpoint: {}", err);
+                        process::exit(1);
+                    }
+                }
+            }
+            None => adaptive_wsp_with_selection(
+                &mut points,
+                obj_nb,
+                args.verbose,
+                args.selection.unwrap_or_default(),
+            ),
+        },
+        (None, Some(_)) => {
+            eprintln!("The --clusters option requires --adaptive");
+            process::exit(1);
+        }
+        (None, None) => {
+            let d_min = match args.relative.as_deref() {
+                None => DMin::Absolute(args.d_min),
+                Some("max") => DMin::Relative(args.d_min),
+                Some("mean") => DMin::RelativeMean(args.d_min),
+                Some(reference) => {
+                    eprintln!("Unknown reference distance: {}", reference);
+                    process::exit(1);
+                }
+            };
+            match (args.nb_passes, args.sweep) {
+                (Some(_), true) => {
+                    eprintln!("The --anneal and --sweep options are incompatible");
+                    process::exit(1);
+                }
+                (Some(nb_passes), false) => {
+                    if nb_passes == 0 || args.cooling <= 0.0 || args.cooling >= 1.0 {
+                        eprintln!(
+                            "The number of passes must be positive and the cooling ratio in (0, 1)"
+                        );
+                        process::exit(1);
+                    }
+                    wsp_annealed(&mut points, d_min, nb_passes, args.cooling)
+                }
+                (None, true) => wsp_sweep(&mut points, d_min),
+                (None, false) => {
+                    if let Some(delta) = args.sensitivity {
+                        println!("{}", sensitivity(&mut points, args.d_min, delta));
+                    }
+                    match args.nb_restarts {
+                        Some(nb_restarts) => wsp_restarts(
+                            &mut points,
+                            d_min,
+                            nb_restarts,
+                            args.selection.unwrap_or_default(),
+                        ),
+                        None => wsp(&mut points, d_min),
+                    }
+                }
+            }
+        }
+    };
+
+    save_results(&args, &points, &stats, manifest);
+}
 
-    let mut points: PointSet = PointSet::init_from_random(args.nb_initial, args.dim, args.seed);
+/// Builds the design of the --stream option, and returns it as a PointSet whose points are all active.
+fn stream_design(args: &Cli) -> Result<(PointSet, RunStats), Box<dyn Error>> {
+    let start = Instant::now();
+    let mut design = StreamingWsp::new(args.d_min);
+    if let Some(summation) = args.summation {
+        design = design.with_summation(summation);
+    }
+    match args.input_file.as_deref() {
+        Some(filename) => {
+            for chunk in CsvChunks::from_path(filename, 4096)? {
+                for candidate in chunk? {
+                    design.offer(candidate)?;
+                }
+            }
+        }
+        None => {
+            let mut rng = args
+                .rng
+                .seeded(derive_seed(args.seed, SeedStream::Generation));
+            for candidate in uniform_candidates(args.dim, &mut rng).take(args.nb_initial) {
+                design.offer(candidate)?;
+            }
+        }
+    }
+    let nb_offered = design.nb_offered();
+    let points = design.into_points();
+    let stats = RunStats {
+        nb_removed: nb_offered - points.len(),
+        nb_iterations: nb_offered,
+        nb_runs: 1,
+        loop_time: start.elapsed().as_secs_f64(),
+        ..Default::default()
+    };
+    let backend = args.backend.unwrap_or(Backend::KdTree);
+    let points = PointSet::init_from_preset_with_backend(points, Metric::Manhattan, backend);
+    Ok((points, stats))
+}
 
-    if let Some(filename) = args.output_file_before {
-        if let Err(err) = points.save_in_csv(&filename, args.transpose) {
-            eprintln!("Error writing in CSV: {}", err);
+/// Exits if the output bounds do not match the dimension of the points.
+fn check_bounds(args: &Cli, points: &PointSet) {
+    if let Some(bounds) = args.bounds.as_ref() {
+        let nb_dim = points.points.first().map_or(0, |p| p.len());
+        if bounds.0.len() != nb_dim {
+            eprintln!(
+                "Expected bounds for {} dimensions, got {}",
+                nb_dim,
+                bounds.0.len()
+            );
             process::exit(1);
         }
     }
+}
+
+/// Records the parameters of the run and the hashes of its input files, before they may change.
+fn input_manifest(args: &Cli) -> Manifest {
+    let mut manifest = Manifest::new();
+    manifest.add("command", env::args().collect::<Vec<String>>().join(" "));
+    manifest.add("parameters", format!("{:?}", args));
+    // A resumed run keeps the seed of the checkpoint, recorded as its origin seed
+    if args.resume.is_none() {
+        manifest.add("seed", args.seed);
+    }
+    let inputs = [
+        ("input_hash", &args.input_file),
+        ("index_hash", &args.load_index),
+        ("checkpoint_hash", &args.resume),
+    ];
+    for (key, filename) in inputs.iter() {
+        if let Some(filename) = filename.as_deref() {
+            if let Err(err) = manifest.add_file_hash(key, filename) {
+                eprintln!("Error reading {}: {}", filename, err);
+                process::exit(1);
+            }
+        }
+    }
+    manifest
+}
+
+/// Paths of the files written at the end of a run.
+struct Outputs {
+    design: String,
+    initial: Option<String>,
+    mask: Option<String>,
+    representatives: Option<String>,
+    transform: String,
+    manifest: String,
+}
+
+impl Outputs {
+    fn new(args: &Cli) -> Outputs {
+        match args.export_prefix.as_deref() {
+            Some(prefix) => Outputs {
+                design: format!("{}.design.csv", prefix),
+                initial: Some(format!("{}.initial.csv", prefix)),
+                mask: Some(format!("{}.mask.csv", prefix)),
+                representatives: Some(format!("{}.representatives.csv", prefix)),
+                transform: format!("{}.transform.csv", prefix),
+                manifest: format!("{}.manifest", prefix),
+            },
+            None => {
+                let output = Path::new(&args.output_file);
+                Outputs {
+                    design: args.output_file.clone(),
+                    initial: None,
+                    mask: None,
+                    representatives: args.representatives_file.clone(),
+                    transform: output
+                        .with_extension("transform.csv")
+                        .to_string_lossy()
+                        .into_owned(),
+                    manifest: output
+                        .with_extension("manifest")
+                        .to_string_lossy()
+                        .into_owned(),
+                }
+            }
+        }
+    }
+}
 
-    match args.nb_target {
-        Some(obj_nb) => adaptive_wsp(&mut points, obj_nb, args.verbose),
-        None => wsp(&mut points, args.d_min),
+/// Stores the design and the requested side files, then the manifest of the run.
+fn save_results(args: &Cli, points: &PointSet, stats: &RunStats, mut manifest: Manifest) {
+    check_bounds(args, points);
+    let outputs = Outputs::new(args);
+    let latinized = args
+        .latinize
+        .then(|| latinize(points, min_distance(points)));
+    let remaining: Vec<&Vec<f64>> = match latinized.as_ref() {
+        Some(design) => design.iter().collect(),
+        None => points.iter_remaining().collect(),
+    };
+    if let Err(err) = save_points(points, &remaining, &outputs.design, args) {
+        eprintln!("Error writing the points: {}", err);
+        process::exit(1);
+    }
+    if let Some(filename) = outputs.initial.as_deref() {
+        let all: Vec<&Vec<f64>> = points.points.iter().collect();
+        if let Err(err) = save_points(points, &all, filename, args) {
+            eprintln!("Error writing the initial points: {}", err);
+            process::exit(1);
+        }
+    }
+    if let Some(filename) = outputs.mask.as_deref() {
+        if let Err(err) = points.save_mask_in_csv(filename) {
+            eprintln!("Error writing the mask: {}", err);
+            process::exit(1);
+        }
+    }
+    if let Some(filename) = outputs.representatives.as_deref() {
+        if let Err(err) = points.save_representatives_in_csv(filename) {
+            eprintln!("Error writing the representatives: {}", err);
+            process::exit(1);
+        }
+    }
+    if let Some(normalization) = points.normalization() {
+        if let Err(err) = normalization.save_in_csv(&outputs.transform) {
+            eprintln!("Error writing the transform: {}", err);
+            process::exit(1);
+        }
+    }
+    if let Some(filename) = args.stats_file.as_deref() {
+        if let Err(err) = stats.save_in_csv(filename) {
+            eprintln!("Error writing the statistics: {}", err);
+            process::exit(1);
+        }
     }
 
-    if let Err(err) = points.save_in_csv(&args.output_file, args.transpose) {
-        eprintln!("Error writing in CSV: {}", err);
+    manifest.add("metric", format!("{:?}", points.metric()));
+    manifest.add("backend", points.backend());
+    manifest.add("summation", points.summation());
+    manifest.add("rng", points.rng_algorithm());
+    manifest.add("origin_seed", points.origin_seed());
+    if let Some(bits) = points.fixed_point() {
+        manifest.add("fixed_point", bits);
+    }
+    manifest.add("nb_points", points.points.len());
+    manifest.add("nb_active", points.nb_active);
+    manifest.add("output", &outputs.design);
+    let hashes = [
+        ("output_hash", Some(&outputs.design)),
+        ("initial_hash", outputs.initial.as_ref()),
+        ("mask_hash", outputs.mask.as_ref()),
+        ("representatives_hash", outputs.representatives.as_ref()),
+    ];
+    let mut result = Ok(());
+    for (key, filename) in hashes.iter() {
+        if let Some(filename) = filename {
+            result = result.and_then(|_| manifest.add_file_hash(key, filename));
+        }
+    }
+    let result = result
+        .map_err(|err| err.into())
+        .and_then(|_| manifest.save(&outputs.manifest));
+    if let Err(err) = result {
+        eprintln!("Error writing the manifest: {}", err);
         process::exit(1);
     }
     if args.verbose {
         println!("Nb active: {}", points.nb_active);
+        println!("{:?}", stats);
+        println!("{}", points.stats());
+    }
+}
+
+/// Only keeps the candidates selected by the --subsample option, if any.
+fn subsample_points(points: Vec<Vec<f64>>, args: &Cli) -> Vec<Vec<f64>> {
+    match args.subsampling {
+        Some(subsampling) => {
+            let idxs = subsample_with_rng(
+                &points,
+                subsampling,
+                &mut args
+                    .rng
+                    .seeded(derive_seed(args.seed, SeedStream::Subsampling)),
+            );
+            idxs.into_iter().map(|i| points[i].clone()).collect()
+        }
+        None => points,
+    }
+}
+
+/// Reads candidate points in the format given by the extension of the file.
+fn read_points(filename: &str) -> Result<Vec<Vec<f64>>, Box<dyn Error>> {
+    if filename.ends_with(".bin") {
+        read_points_binary(filename)
+    } else {
+        read_points_csv(filename)
+    }
+}
+
+/// Stores points of the PointSet in the format given by the extension of the file,
+/// mapped onto the --bounds or onto the --output-scale.
+fn save_points(
+    points: &PointSet,
+    selection: &[&Vec<f64>],
+    filename: &str,
+    args: &Cli,
+) -> Result<(), Box<dyn Error>> {
+    let scaling = match (args.bounds.as_ref(), points.normalization()) {
+        (Some(bounds), _) => Some(Normalization {
+            lower: bounds.0.iter().map(|b| b.0).collect(),
+            upper: bounds.0.iter().map(|b| b.1).collect(),
+        }),
+        (None, Some(normalization)) if args.output_scale == OutputScale::Original => {
+            Some(normalization.clone())
+        }
+        _ => None,
+    };
+    let selection: Vec<Vec<f64>> = match scaling {
+        Some(scaling) => selection.iter().map(|p| scaling.denormalize(p)).collect(),
+        None => selection.iter().map(|&p| p.clone()).collect(),
+    };
+    if filename.ends_with(".bin") {
+        write_points_binary(&selection, filename, args.transpose)
+    } else {
+        write_points_csv(&selection, filename, args.transpose)
+    }
+}
+
+/// Ranges of the dimensions of the output points, written "0:10,-5:5"
+#[derive(Debug)]
+struct Bounds(Vec<(f64, f64)>);
+
+impl FromStr for Bounds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Bounds, String> {
+        s.split(',')
+            .map(|range| {
+                let (lower, upper) = range.split_once(':').ok_or_else(|| {
+                    format!("Invalid range \"{}\", expected <lower>:<upper>", range)
+                })?;
+                let parse = |x: &str| {
+                    x.trim()
+                        .parse::<f64>()
+                        .map_err(|_| format!("Invalid bound \"{}\"", x))
+                };
+                Ok((parse(lower)?, parse(upper)?))
+            })
+            .collect::<Result<_, String>>()
+            .map(Bounds)
     }
 }
@@ -0,0 +1,116 @@
+//! Reproducibility manifests: a record of everything needed to regenerate a design
+//! (crate version, parameters, seeds, metric...) together with hashes of the input and output
+//! files, to check later that a regenerated design is identical bit-for-bit.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Returns the 64-bit FNV-1a hash of the content of a file, as a hexadecimal string.
+/// The hash is not cryptographic: it detects accidental changes, not malicious ones.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the file to hash.
+pub fn hash_file(filepath: &str) -> io::Result<String> {
+    let mut rdr = BufReader::new(File::open(filepath)?);
+    let mut buf = [0u8; 1 << 16];
+    let mut hash = FNV_OFFSET;
+    loop {
+        let nb_read = rdr.read(&mut buf)?;
+        if nb_read == 0 {
+            break;
+        }
+        for &byte in buf[..nb_read].iter() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    Ok(format!("{:016x}", hash))
+}
+
+/// Ordered list of `key = "value"` entries describing how a design was generated.
+/// The version of the crate is always the first entry.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(100, 10, 51);
+/// wsp::wsp(&mut points, 1.0);
+/// points.save_in_csv("wsp_design.csv", false).unwrap();
+///
+/// let mut manifest = wsp::Manifest::new();
+/// manifest.add("seed", 51);
+/// manifest.add("metric", format!("{:?}", points.metric()));
+/// manifest.add_file_hash("output_hash", "wsp_design.csv").unwrap();
+/// manifest.save("wsp_design.manifest").unwrap();
+/// # std::fs::remove_file("wsp_design.csv").unwrap();
+/// # std::fs::remove_file("wsp_design.manifest").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifest {
+    entries: Vec<(String, String)>,
+}
+
+impl Default for Manifest {
+    fn default() -> Manifest {
+        Manifest::new()
+    }
+}
+
+impl Manifest {
+    /// Creates a manifest containing the version of the crate.
+    pub fn new() -> Manifest {
+        Manifest {
+            entries: vec![(
+                "wsp_version".to_string(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            )],
+        }
+    }
+
+    /// Adds an entry to the manifest.
+    pub fn add(&mut self, key: &str, value: impl ToString) {
+        self.entries.push((key.to_string(), value.to_string()));
+    }
+
+    /// Adds an entry with the hash of the content of a file (see [`hash_file`]).
+    pub fn add_file_hash(&mut self, key: &str, filepath: &str) -> io::Result<()> {
+        let hash = hash_file(filepath)?;
+        self.add(key, hash);
+        Ok(())
+    }
+
+    /// Returns the entries of the manifest, in insertion order.
+    pub fn entries(&self) -> &[(String, String)] {
+        &self.entries
+    }
+
+    /// Stores the manifest in a text file, with one `key = "value"` line per entry.
+    pub fn save(&self, filepath: &str) -> Result<(), Box<dyn Error>> {
+        let mut wrt = BufWriter::new(File::create(filepath)?);
+        for (key, value) in self.entries.iter() {
+            writeln!(wrt, "{} = {:?}", key, value)?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file() {
+        let filepath = "wsp_test_hash.txt";
+        std::fs::write(filepath, "").unwrap();
+        assert_eq!(hash_file(filepath).unwrap(), "cbf29ce484222325");
+        std::fs::write(filepath, "a").unwrap();
+        assert_eq!(hash_file(filepath).unwrap(), "af63dc4c8601ec8c");
+        std::fs::remove_file(filepath).unwrap();
+    }
+}
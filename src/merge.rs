@@ -0,0 +1,188 @@
+//! Merging of designs coming from different campaigns over the same domain,
+//! e.g., cheap (low-fidelity) and expensive (high-fidelity) simulations, and union of candidate sets
+//! coming from different sources.
+
+use crate::kdtree::KdTree;
+use crate::{wsp, DMin, PointSet, WspError};
+
+impl PointSet {
+    /// Combines the active points of a low-fidelity and a high-fidelity design in a new PointSet.
+    /// High-fidelity points are all kept (and stay fixed in the resulting PointSet), while low-fidelity points
+    /// closer than `d_min` to a high-fidelity point, or to another low-fidelity point, are removed.
+    ///
+    /// In the resulting PointSet, the first points are the high-fidelity points, in the same order as
+    /// in `high_fidelity`, followed by the low-fidelity points. The metric and the backend of the
+    /// high-fidelity design are used.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_fidelity` - The design of the cheap experiments.
+    /// * `high_fidelity` - The design of the expensive experiments.
+    /// * `d_min` - The minimal distance between low-fidelity points and all other points. See [`wsp`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut low = wsp::PointSet::init_from_random(1000, 5, 51);
+    /// wsp::wsp(&mut low, 0.8);
+    /// let mut high = wsp::PointSet::init_from_random(200, 5, 52);
+    /// wsp::wsp(&mut high, 1.5);
+    ///
+    /// let merged = wsp::PointSet::merge(&low, &high, 0.8).unwrap();
+    /// assert!(merged.active.iter().take(high.nb_active).all(|a| a));
+    /// ```
+    pub fn merge(
+        low_fidelity: &PointSet,
+        high_fidelity: &PointSet,
+        d_min: impl Into<DMin>,
+    ) -> Result<PointSet, WspError> {
+        let high = high_fidelity.get_remaining();
+        let low = low_fidelity.get_remaining();
+        let nb_dim = high.first().or(low.first()).map_or(0, |p| p.len());
+        if let Some(point) = high.iter().chain(low.iter()).find(|p| p.len() != nb_dim) {
+            return Err(WspError::DimensionMismatch {
+                expected: nb_dim,
+                found: point.len(),
+            });
+        }
+
+        let nb_high = high.len();
+        let points: Vec<Vec<f64>> = high.into_iter().chain(low).collect();
+        if points.is_empty() {
+            return Ok(PointSet::init_from_preset(points));
+        }
+        let mut merged = PointSet::init_from_preset_with_backend(
+            points,
+            high_fidelity.metric,
+            high_fidelity.backend,
+        );
+        merged.set_fixed(&(0..nb_high).collect::<Vec<usize>>());
+        wsp(&mut merged, d_min);
+        Ok(merged)
+    }
+
+    /// Concatenates all the candidate points of two PointSets in a new PointSet, to thin them jointly.
+    /// The points of `a` come first, followed by the points of `b`. The metric of `a` is used, and the
+    /// backend is chosen from the size of the union.
+    ///
+    /// With a deduplication tolerance, a point closer than the tolerance to a previous point of the union
+    /// is dropped, such that near-identical points coming from both sets are only kept once.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The first set of candidate points.
+    /// * `b` - The second set of candidate points.
+    /// * `dedup_tolerance` - If set, the distance under which two points are considered identical.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let a = wsp::PointSet::init_from_preset(vec![vec![0.0, 0.0], vec![1.0, 1.0]]);
+    /// let b = wsp::PointSet::init_from_preset(vec![vec![1.0, 1.0 + 1e-12], vec![0.5, 0.5]]);
+    ///
+    /// let union = wsp::PointSet::union(&a, &b, Some(1e-9)).unwrap();
+    /// assert_eq!(union.points.len(), 3);
+    /// ```
+    pub fn union(
+        a: &PointSet,
+        b: &PointSet,
+        dedup_tolerance: Option<f64>,
+    ) -> Result<PointSet, WspError> {
+        let nb_dim = a.points.first().or(b.points.first()).map_or(0, |p| p.len());
+        if let Some(point) = a
+            .points
+            .iter()
+            .chain(b.points.iter())
+            .find(|p| p.len() != nb_dim)
+        {
+            return Err(WspError::DimensionMismatch {
+                expected: nb_dim,
+                found: point.len(),
+            });
+        }
+
+        let mut points: Vec<Vec<f64>> = a.points.iter().chain(b.points.iter()).cloned().collect();
+        if let Some(tolerance) = dedup_tolerance {
+            let tree = KdTree::new(&points);
+            let mut kept = vec![true; points.len()];
+            for i in 0..points.len() {
+                if kept[i] {
+                    for j in tree.within(&points, a.metric, a.summation, &points[i], tolerance) {
+                        if j > i {
+                            kept[j] = false;
+                        }
+                    }
+                }
+            }
+            let mut kept = kept.into_iter();
+            points.retain(|_| kept.next().unwrap());
+        }
+        Ok(PointSet::init_from_preset_with_metric(points, a.metric))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_separation() {
+        let d_min = 0.1;
+        let mut low = PointSet::init_from_random(500, 2, 51);
+        wsp(&mut low, d_min);
+        let mut high = PointSet::init_from_random(100, 2, 52);
+        wsp(&mut high, 0.3);
+
+        let merged = PointSet::merge(&low, &high, d_min).unwrap();
+        let nb_high = high.nb_active;
+        assert_eq!(merged.points.len(), nb_high + low.nb_active);
+        assert!(merged.active.iter().take(nb_high).all(|a| a));
+        assert!(merged.nb_active < merged.points.len());
+
+        // Low-fidelity points are far enough from all other points
+        for i in nb_high..merged.points.len() {
+            for j in 0..merged.points.len() {
+                if i != j && merged.active[i] && merged.active[j] {
+                    assert!(merged.distance(i, j) >= d_min);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_union_deduplication() {
+        let a = PointSet::init_from_random(300, 3, 51);
+        let b = PointSet::init_from_random(300, 3, 51);
+        let c = PointSet::init_from_random(200, 3, 52);
+
+        assert_eq!(PointSet::union(&a, &c, None).unwrap().points.len(), 500);
+        let union = PointSet::union(&a, &b, Some(1e-9)).unwrap();
+        assert_eq!(union.points, a.points);
+        assert_eq!(union.nb_active, 300);
+
+        // Near-copies of the first points of a
+        let copies: Vec<Vec<f64>> = a.points[..50]
+            .iter()
+            .map(|p| p.iter().map(|x| x + 1e-7).collect())
+            .chain(c.points[..100].iter().cloned())
+            .collect();
+        let copies = PointSet::init_from_preset(copies);
+        let union = PointSet::union(&a, &copies, Some(1e-5)).unwrap();
+        assert_eq!(union.points.len(), 400);
+        assert_eq!(union.points[..300], a.points[..]);
+        assert_eq!(union.points[300..], c.points[..100]);
+    }
+
+    #[test]
+    fn test_merge_dimension_mismatch() {
+        let low = PointSet::init_from_random(10, 2, 51);
+        let high = PointSet::init_from_random(10, 3, 51);
+        assert_eq!(
+            PointSet::merge(&low, &high, 0.1).err(),
+            Some(WspError::DimensionMismatch {
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Handling of missing values (NaN coordinates) in the candidate points.
+
+use crate::{Metric, PointSet, WspError};
+
+/// Policy applied to the missing values (NaN coordinates) of the candidate points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissingValues {
+    /// Returns an error if any coordinate is missing.
+    Reject,
+    /// Replaces each missing coordinate by the mean of the known values of its dimension.
+    ImputeMean,
+    /// Computes the distance between two points only on the coordinates known for both points,
+    /// renormalized to the full dimension. The PointSet always uses the dense backend, whatever
+    /// the backend it is created or rebuilt with.
+    Skip,
+}
+
+impl PointSet {
+    /// Creates a 'PointSet' from an already initialised vector of points that may contain missing values.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The pre-initialised set of points. Missing coordinates are NaN.
+    /// * `metric` - The distance used between the points.
+    /// * `policy` - What to do with the missing coordinates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use wsp::{Metric, MissingValues, PointSet};
+    ///
+    /// let points = vec![vec![0.0, f64::NAN], vec![1.0, 1.0], vec![0.5, 0.0]];
+    /// assert!(PointSet::init_from_preset_with_missing(points.clone(), Metric::Manhattan, MissingValues::Reject).is_err());
+    ///
+    /// let pointset = PointSet::init_from_preset_with_missing(points, Metric::Manhattan, MissingValues::ImputeMean).unwrap();
+    /// assert_eq!(pointset.points[0], vec![0.0, 0.5]);
+    /// ```
+    pub fn init_from_preset_with_missing(
+        mut points: Vec<Vec<f64>>,
+        metric: Metric,
+        policy: MissingValues,
+    ) -> Result<PointSet, WspError> {
+        let first_missing = match first_missing(&points) {
+            None => return Ok(PointSet::init_from_preset_with_metric(points, metric)),
+            Some(err) => err,
+        };
+
+        match policy {
+            MissingValues::Reject => Err(first_missing),
+            MissingValues::ImputeMean => {
+                impute_mean(&mut points)?;
+                Ok(PointSet::init_from_preset_with_metric(points, metric))
+            }
+            MissingValues::Skip => {
+                let (distance_matrix, d_min, d_max) = PointSet::compute_distance_matrix(
+                    &points,
+                    Some(&move |p1, p2| skipping_distance(metric, p1, p2)),
+                );
+                for (i, row) in distance_matrix.iter().enumerate() {
+                    if let Some(j) = row.iter().position(|d| d.is_nan()) {
+                        return Err(WspError::NoCommonCoordinate(i, j));
+                    }
+                }
+
+                Ok(PointSet::init_from_distance_matrix(
+                    points,
+                    distance_matrix,
+                    d_min,
+                    d_max,
+                    metric,
+                ))
+            }
+        }
+    }
+}
+
+/// Returns the error locating the first missing coordinate of the points, if any.
+pub(crate) fn first_missing(points: &[Vec<f64>]) -> Option<WspError> {
+    points.iter().enumerate().find_map(|(i, point)| {
+        point
+            .iter()
+            .position(|x| x.is_nan())
+            .map(|dim| WspError::MissingValue { point: i, dim })
+    })
+}
+
+/// Replaces the missing coordinates by the mean of the known values of their dimension.
+fn impute_mean(points: &mut [Vec<f64>]) -> Result<(), WspError> {
+    let nb_dim = points.first().map_or(0, |p| p.len());
+    for dim in 0..nb_dim {
+        let known: Vec<f64> = points
+            .iter()
+            .map(|p| p[dim])
+            .filter(|x| !x.is_nan())
+            .collect();
+        if known.is_empty() {
+            return Err(WspError::NoKnownValue(dim));
+        }
+        let mean = known.iter().sum::<f64>() / known.len() as f64;
+        points
+            .iter_mut()
+            .filter(|p| p[dim].is_nan())
+            .for_each(|p| p[dim] = mean);
+    }
+    Ok(())
+}
+
+/// Distance computed on the coordinates known for both points, renormalized as if all
+/// coordinates were known. Returns NaN if the points do not share any known coordinate.
+pub(crate) fn skipping_distance(metric: Metric, p1: &[f64], p2: &[f64]) -> f64 {
+    let (q1, q2): (Vec<f64>, Vec<f64>) = p1
+        .iter()
+        .zip(p2.iter())
+        .filter(|(x1, x2)| !x1.is_nan() && !x2.is_nan())
+        .unzip();
+    if q1.is_empty() {
+        return f64::NAN;
+    }
+    let scale = p1.len() as f64 / q1.len() as f64;
+    match metric {
+        Metric::Manhattan => metric.distance(&q1, &q2) * scale,
+        Metric::Euclidean => metric.distance(&q1, &q2) * scale.sqrt(),
+        // An angle does not depend on the number of dimensions
+        Metric::Geodesic => metric.distance(&q1, &q2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wsp, Backend, Summation};
+
+    #[test]
+    fn test_skipping_distance() {
+        let p1 = vec![0.0, f64::NAN, 1.0, 0.0];
+        let p2 = vec![1.0, 0.0, f64::NAN, 0.5];
+        assert_eq!(skipping_distance(Metric::Manhattan, &p1, &p2), 3.0);
+        assert!(skipping_distance(Metric::Manhattan, &[f64::NAN, 0.0], &[0.0, f64::NAN]).is_nan());
+    }
+
+    #[test]
+    fn test_skip_policy() {
+        let mut points = PointSet::init_from_random(200, 3, 51).points;
+        points[3][1] = f64::NAN;
+        points[42][0] = f64::NAN;
+        let mut pointset = PointSet::init_from_preset_with_missing(
+            points.clone(),
+            Metric::Manhattan,
+            MissingValues::Skip,
+        )
+        .unwrap();
+        wsp(&mut pointset, 0.3);
+        assert!(pointset.nb_active > 0);
+
+        // The policy is kept when the dense structures are rebuilt
+        let mut rebuilt = PointSet::init_from_preset_with_missing(
+            points.clone(),
+            Metric::Manhattan,
+            MissingValues::Skip,
+        )
+        .unwrap()
+        .with_summation(Summation::Neumaier);
+        wsp(&mut rebuilt, 0.3);
+        assert_eq!(rebuilt.active, pointset.active);
+
+        // Other backends fall back to the dense one
+        let mut moved = PointSet::init_from_preset_with_missing(
+            points.clone(),
+            Metric::Manhattan,
+            MissingValues::Skip,
+        )
+        .unwrap()
+        .with_backend(Backend::KdTree);
+        assert_eq!(moved.backend(), Backend::Dense);
+        wsp(&mut moved, 0.3);
+        assert_eq!(moved.active, pointset.active);
+
+        // Same policy without choosing it
+        let mut plain =
+            PointSet::init_from_preset_with_backend(points, Metric::Manhattan, Backend::Grid);
+        assert_eq!(plain.backend(), Backend::Dense);
+        wsp(&mut plain, 0.3);
+        assert_eq!(plain.active, pointset.active);
+    }
+
+    #[test]
+    fn test_missing_value_errors() {
+        let points = vec![vec![0.0, f64::NAN], vec![1.0, f64::NAN]];
+        assert_eq!(
+            PointSet::init_from_preset_with_missing(
+                points.clone(),
+                Metric::Manhattan,
+                MissingValues::ImputeMean
+            )
+            .err(),
+            Some(WspError::NoKnownValue(1))
+        );
+        assert_eq!(
+            PointSet::init_from_preset_normalized(points.clone()).err(),
+            Some(WspError::MissingValue { point: 0, dim: 1 })
+        );
+        assert_eq!(
+            PointSet::init_from_preset_with_missing(
+                vec![vec![0.0, f64::NAN], vec![f64::NAN, 1.0]],
+                Metric::Manhattan,
+                MissingValues::Skip
+            )
+            .err(),
+            Some(WspError::NoCommonCoordinate(0, 1))
+        );
+
+        // Without a policy, points without any known coordinate in common are never too close
+        let mut plain = PointSet::init_from_preset(vec![
+            vec![0.0, f64::NAN],
+            vec![f64::NAN, 0.0],
+            vec![0.0, 0.1],
+        ]);
+        wsp(&mut plain, 0.5);
+        assert_eq!(plain.nb_active, 2);
+    }
+}
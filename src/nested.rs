@@ -0,0 +1,59 @@
+//! Nested designs: a sequence of designs where each design contains the previous one
+//! (e.g. 50 ⊂ 100 ⊂ 200 points), for multi-fidelity and staged experiments.
+
+use crate::{adaptive_wsp, PointSet};
+
+/// Builds a sequence of nested designs of increasing sizes.
+/// Each design is computed with the adaptive WSP algorithm, while forcing the points of the
+/// previous design to stay active. The resulting PointSet contains the largest design.
+/// Returns, for each requested size, the indexes of the points of the design.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `sizes` - The desired number of points of each design, in increasing order.
+/// * `verbose` - Print running information about the iterations of the algorithm.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+/// let designs = wsp::nested_wsp(&mut points, &[20, 50, 100], false);
+/// assert!(designs[0].iter().all(|i| designs[1].contains(i)));
+/// assert!(designs[1].iter().all(|i| designs[2].contains(i)));
+/// ```
+pub fn nested_wsp(set: &mut PointSet, sizes: &[usize], verbose: bool) -> Vec<Vec<usize>> {
+    let mut designs: Vec<Vec<usize>> = Vec::with_capacity(sizes.len());
+    for &size in sizes.iter() {
+        if verbose {
+            println!("Nested design with {} points", size);
+        }
+        set.reset_reseach_params();
+        if let Some(previous) = designs.last() {
+            set.set_fixed(previous);
+        }
+        adaptive_wsp(set, size, verbose);
+
+        designs.push((0..set.points.len()).filter(|&i| set.active[i]).collect());
+    }
+    set.clear_fixed();
+    designs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_designs() {
+        let mut points = PointSet::init_from_random(500, 3, 51);
+        let designs = nested_wsp(&mut points, &[10, 40, 80], false);
+
+        assert_eq!(designs.len(), 3);
+        for pair in designs.windows(2) {
+            assert!(pair[0].iter().all(|i| pair[1].contains(i)));
+            assert!(pair[0].len() < pair[1].len());
+        }
+        assert_eq!(designs[2].len(), points.nb_active);
+    }
+}
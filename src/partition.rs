@@ -0,0 +1,86 @@
+//! Partition of a design into disjoint groups that are each (approximately) space-filling,
+//! e.g. for cross-validation folds or parallel batches of experiments.
+
+use crate::PointSet;
+
+/// Splits the active points of the PointSet into `k` disjoint groups of (almost) equal sizes,
+/// using a greedy round-robin maximin assignment: the groups pick a point in turn, each time the
+/// remaining point maximizing its distance to the closest point already in the group.
+/// An empty group picks the remaining point farthest from all the points already assigned.
+///
+/// Returns, for each group, the indexes of its points.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance.
+/// * `k` - The number of groups.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+/// wsp::wsp(&mut points, 2.0);
+/// let folds = wsp::partition_points(&points, 5);
+/// assert_eq!(folds.len(), 5);
+/// assert_eq!(folds.iter().map(|f| f.len()).sum::<usize>(), points.nb_active);
+/// ```
+pub fn partition_points(set: &PointSet, k: usize) -> Vec<Vec<usize>> {
+    assert!(k > 0, "The number of groups must be positive");
+    let mut remaining: Vec<usize> = (0..set.points.len()).filter(|&i| set.active[i]).collect();
+    let mut groups: Vec<Vec<usize>> = vec![Vec::with_capacity(remaining.len() / k + 1); k];
+
+    // Distance of each remaining point to the closest point of each group, and of all groups
+    let mut closest: Vec<Vec<f64>> = vec![vec![f64::INFINITY; remaining.len()]; k];
+    let mut closest_any: Vec<f64> = vec![f64::INFINITY; remaining.len()];
+
+    let mut group = 0;
+    while !remaining.is_empty() {
+        let distances = if groups[group].is_empty() {
+            &closest_any
+        } else {
+            &closest[group]
+        };
+        let next = distances
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap()
+            .0;
+        let chosen = remaining.swap_remove(next);
+        closest_any.swap_remove(next);
+        closest.iter_mut().for_each(|c| {
+            c.swap_remove(next);
+        });
+        groups[group].push(chosen);
+
+        for (pos, &i) in remaining.iter().enumerate() {
+            let d = set.distance(i, chosen);
+            closest[group][pos] = closest[group][pos].min(d);
+            closest_any[pos] = closest_any[pos].min(d);
+        }
+        group = (group + 1) % k;
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partition_points() {
+        let points: Vec<Vec<f64>> = (0..8).map(|i| vec![i as f64]).collect();
+        let mut pointset = PointSet::init_from_preset(points);
+        pointset.active.set(3, false);
+        pointset.nb_active -= 1;
+
+        let mut groups = partition_points(&pointset, 2);
+        groups.iter_mut().for_each(|g| g.sort_unstable());
+        assert_eq!(groups, vec![vec![1, 4, 5, 7], vec![0, 2, 6]]);
+
+        // Each group spans the whole line
+        for group in groups.iter() {
+            assert!(group.iter().any(|&i| i <= 1) && group.iter().any(|&i| i >= 6));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Profile of the number of remaining points as a function of the minimal distance.
+//! The profile is a step function that roughly decreases with the distance: WSP is not monotone,
+//! and a larger distance may keep a few more points. It helps choosing a distance (or a number
+//! of points) while understanding the trade-off, instead of relying on the adaptive search.
+
+use crate::{wsp, PointSet};
+use std::fmt;
+
+/// Executes the WSP algorithm for `nb_steps` distances evenly spaced between `from` and `to`
+/// (both included), and returns the number of remaining points for each distance.
+/// The distance matrix and the sorted neighbours are computed once and reused for all runs.
+/// The PointSet is reset after the profile, as if the algorithm had not been executed.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `from` - The first distance of the profile.
+/// * `to` - The last distance of the profile.
+/// * `nb_steps` - The number of distances of the profile.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+/// let profile = wsp::distance_profile(&mut points, 0.5, 3.0, 6);
+/// assert_eq!(profile.len(), 6);
+/// assert_eq!(profile[0].0, 0.5);
+/// assert_eq!(profile[5].0, 3.0);
+/// ```
+pub fn distance_profile(
+    set: &mut PointSet,
+    from: f64,
+    to: f64,
+    nb_steps: usize,
+) -> Vec<(f64, usize)> {
+    let distances: Vec<f64> = match nb_steps {
+        0 => Vec::new(),
+        1 => vec![from],
+        _ => (0..nb_steps)
+            .map(|step| from + (to - from) * step as f64 / (nb_steps - 1) as f64)
+            .collect(),
+    };
+    let profile = distances
+        .into_iter()
+        .map(|d| {
+            set.reset_reseach_params();
+            wsp(set, d);
+            (d, set.nb_active)
+        })
+        .collect();
+    set.reset_reseach_params();
+    profile
+}
+
+/// Design obtained with a perturbed minimal distance, compared to the reference design.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Perturbation {
+    /// The perturbed minimal distance.
+    pub d_min: f64,
+    /// The number of points of the perturbed design.
+    pub nb_active: usize,
+    /// The number of points of the perturbed design that are not in the reference design.
+    pub nb_entering: usize,
+    /// The number of points of the reference design that are not in the perturbed design.
+    pub nb_leaving: usize,
+}
+
+/// Sensitivity of a design to perturbations of its minimal distance. See [`sensitivity`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensitivity {
+    /// The reference minimal distance.
+    pub d_min: f64,
+    /// The number of points of the reference design.
+    pub nb_active: usize,
+    /// The design with the minimal distance decreased by delta.
+    pub lower: Perturbation,
+    /// The design with the minimal distance increased by delta.
+    pub upper: Perturbation,
+}
+
+impl Sensitivity {
+    /// Largest fraction of the reference design that changes (entering and leaving points)
+    /// with one of the perturbations. A large value means that the minimal distance sits on a cliff.
+    pub fn max_change(&self) -> f64 {
+        let change = |p: &Perturbation| (p.nb_entering + p.nb_leaving) as f64;
+        change(&self.lower).max(change(&self.upper)) / self.nb_active.max(1) as f64
+    }
+}
+
+impl fmt::Display for Sensitivity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "d_min = {}: {} points", self.d_min, self.nb_active)?;
+        for p in [&self.lower, &self.upper] {
+            writeln!(
+                f,
+                "d_min = {}: {} points ({} entering, {} leaving)",
+                p.d_min, p.nb_active, p.nb_entering, p.nb_leaving
+            )?;
+        }
+        write!(f, "Largest change: {:.1}%", 100.0 * self.max_change())
+    }
+}
+
+/// Executes the WSP algorithm with the minimal distances `d_min - delta`, `d_min` and `d_min + delta`,
+/// and reports how many points enter and leave the design with each perturbation. The number of points
+/// is a step function of the minimal distance: a small perturbation changing many points warns that
+/// the chosen distance sits on a cliff. The PointSet is reset afterwards, as if the algorithm had not been executed.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The reference minimal distance.
+/// * `delta` - The perturbation of the minimal distance. The lower distance is clamped to 0.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+/// let sensitivity = wsp::sensitivity(&mut points, 1.0, 0.05);
+/// assert_eq!(sensitivity.lower.d_min, 0.95);
+/// assert_eq!(sensitivity.upper.d_min, 1.05);
+/// // The perturbed design is the reference one, plus the entering points, minus the leaving ones
+/// let lower = sensitivity.lower;
+/// assert_eq!(lower.nb_active + lower.nb_leaving, sensitivity.nb_active + lower.nb_entering);
+/// println!("{}", sensitivity);
+/// ```
+pub fn sensitivity(set: &mut PointSet, d_min: f64, delta: f64) -> Sensitivity {
+    assert!(
+        delta >= 0.0,
+        "The perturbation must be positive, got {}",
+        delta
+    );
+    let mut design = |d: f64| {
+        set.reset_reseach_params();
+        wsp(set, d);
+        set.active.clone()
+    };
+    let reference = design(d_min);
+    let mut perturbation = |d: f64| {
+        let active = design(d);
+        let nb_entering = (0..active.len())
+            .filter(|&i| active[i] && !reference[i])
+            .count();
+        let nb_leaving = (0..active.len())
+            .filter(|&i| !active[i] && reference[i])
+            .count();
+        Perturbation {
+            d_min: d,
+            nb_active: active.count_ones(),
+            nb_entering,
+            nb_leaving,
+        }
+    };
+    let lower = perturbation((d_min - delta).max(0.0));
+    let upper = perturbation(d_min + delta);
+    set.reset_reseach_params();
+    Sensitivity {
+        d_min,
+        nb_active: reference.count_ones(),
+        lower,
+        upper,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_matches_single_runs() {
+        let points = PointSet::init_from_random(300, 3, 51).points;
+        let mut set = PointSet::init_from_preset(points.clone());
+        let profile = distance_profile(&mut set, 0.1, 0.5, 5);
+        assert_eq!(set.nb_active, 300);
+
+        for &(d, nb_active) in profile.iter() {
+            let mut single = PointSet::init_from_preset(points.clone());
+            wsp(&mut single, d);
+            assert_eq!(single.nb_active, nb_active);
+        }
+    }
+
+    #[test]
+    fn test_sensitivity() {
+        let mut set = PointSet::init_from_random(300, 3, 51);
+        let result = sensitivity(&mut set, 0.3, 0.0);
+        assert_eq!(set.nb_active, 300);
+        assert_eq!(result.lower.nb_entering + result.lower.nb_leaving, 0);
+        assert_eq!(result.max_change(), 0.0);
+
+        let result = sensitivity(&mut set, 0.3, 0.05);
+        let profile = distance_profile(&mut set, 0.25, 0.35, 3);
+        assert_eq!(result.lower.nb_active, profile[0].1);
+        assert_eq!(result.nb_active, profile[1].1);
+        assert_eq!(result.upper.nb_active, profile[2].1);
+        for p in [result.lower, result.upper] {
+            assert_eq!(p.nb_active, result.nb_active + p.nb_entering - p.nb_leaving);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Quantized storage of the distance matrix of the dense backend. Each distance is stored as a
+//! 16-bit or 8-bit code relative to the maximal distance, cutting the memory of the matrix by 4 or 8.
+//! The codes decide whether a pair of points is closer than the minimal distance, except when the
+//! distance is within a quantization step of the minimal distance: the exact distance is then recomputed.
+
+use crate::{Backend, DistanceFn, Metric, PointSet};
+
+/// Default number of neighbours kept in each sorted list of a quantized PointSet, extended on demand:
+/// full lists would take 8 bytes per pair of points, more than the quantized matrix itself.
+pub const QUANTIZED_NB_NEIGHBOURS: usize = 64;
+use std::fmt;
+use std::str::FromStr;
+
+/// Precision of the quantized distances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantization {
+    /// 16-bit codes: 4 times less memory than 64-bit floats, with exact recomputations being rare.
+    U16,
+    /// 8-bit codes: 8 times less memory than 64-bit floats, with more exact recomputations.
+    U8,
+}
+
+impl Quantization {
+    /// Largest code.
+    fn max_code(self) -> u16 {
+        match self {
+            Quantization::U16 => u16::MAX,
+            Quantization::U8 => u8::MAX as u16,
+        }
+    }
+}
+
+impl fmt::Display for Quantization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Quantization::U16 => "u16",
+            Quantization::U8 => "u8",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Quantization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Quantization, String> {
+        match s {
+            "u16" => Ok(Quantization::U16),
+            "u8" => Ok(Quantization::U8),
+            _ => Err(format!("Unknown quantization: {}", s)),
+        }
+    }
+}
+
+enum Codes {
+    U16(Vec<u16>),
+    U8(Vec<u8>),
+}
+
+impl Codes {
+    fn new(quantization: Quantization, len: usize) -> Codes {
+        match quantization {
+            Quantization::U16 => Codes::U16(vec![0; len]),
+            Quantization::U8 => Codes::U8(vec![0; len]),
+        }
+    }
+
+    fn set(&mut self, idx: usize, code: u16) {
+        match self {
+            Codes::U16(codes) => codes[idx] = code,
+            Codes::U8(codes) => codes[idx] = code as u8,
+        }
+    }
+}
+
+/// Row-major matrix of quantized distances. The code of a distance `d` is `floor(d / step)`,
+/// capped to the largest code.
+pub(crate) struct QuantizedMatrix {
+    quantization: Quantization,
+    nb_points: usize,
+    step: f64,
+    codes: Codes,
+}
+
+impl QuantizedMatrix {
+    /// Empty matrix whose largest code stands for the distance `scale`.
+    fn with_scale(nb_points: usize, scale: f64, quantization: Quantization) -> QuantizedMatrix {
+        QuantizedMatrix {
+            quantization,
+            nb_points,
+            step: scale / quantization.max_code() as f64,
+            codes: Codes::new(quantization, nb_points * nb_points),
+        }
+    }
+
+    fn set(&mut self, i: usize, j: usize, d: f64) {
+        let max_code = self.quantization.max_code();
+        // NaN distances (points without any known coordinate in common) get the largest code
+        let code = match self.step > 0.0 {
+            true => (d / self.step).floor().min(max_code as f64) as u16,
+            false => 0,
+        };
+        self.codes.set(i * self.nb_points + j, code);
+    }
+
+    /// Quantizes an exact distance matrix, releasing each row once it is coded.
+    fn from_matrix(
+        distance_matrix: Vec<Vec<f64>>,
+        d_max: f64,
+        quantization: Quantization,
+    ) -> QuantizedMatrix {
+        let mut quantized = QuantizedMatrix::with_scale(distance_matrix.len(), d_max, quantization);
+        for (i, row) in distance_matrix.into_iter().enumerate() {
+            row.into_iter()
+                .enumerate()
+                .for_each(|(j, d)| quantized.set(i, j, d));
+        }
+        quantized
+    }
+
+    /// Computes the quantized distance matrix of the points, with the minimal and maximal distances,
+    /// coding each distance as soon as it is computed: the exact matrix is never stored. The maximal
+    /// distance is only known at the end, so the codes are relative to an upper bound of the distances
+    /// derived from the bounding box of the points.
+    pub(crate) fn from_points(
+        points: &[Vec<f64>],
+        distance_algo: &DistanceFn,
+        metric: Metric,
+        quantization: Quantization,
+    ) -> (QuantizedMatrix, f64, f64) {
+        let scale = crate::diameter_bound(points, metric);
+        let mut quantized = QuantizedMatrix::with_scale(points.len(), scale, quantization);
+        let (d_min, d_max) = PointSet::for_each_distance(points, Some(distance_algo), |i, j, d| {
+            quantized.set(i, j, d);
+            quantized.set(j, i, d);
+        });
+        (quantized, d_min, d_max)
+    }
+
+    fn code(&self, i: usize, j: usize) -> u16 {
+        let idx = i * self.nb_points + j;
+        match &self.codes {
+            Codes::U16(codes) => codes[idx],
+            Codes::U8(codes) => codes[idx] as u16,
+        }
+    }
+
+    /// Whether the distance between points `i` and `j` is strictly lower than `threshold`,
+    /// or None if the code is too close to the threshold to decide. The margin of one step
+    /// on each side covers the rounding errors of the quantization. The largest code only gives
+    /// a lower bound of the distance.
+    fn is_closer(&self, i: usize, j: usize, threshold: f64) -> Option<bool> {
+        let code = self.code(i, j);
+        let lower = (code as f64 - 1.0) * self.step;
+        if code < self.quantization.max_code() && (code as f64 + 2.0) * self.step < threshold {
+            Some(true)
+        } else if lower >= threshold {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+impl PointSet {
+    /// Returns the same PointSet storing its distance matrix with quantized codes instead of 64-bit
+    /// floats (see [`Quantization`]). The result of the algorithm does not change: distances close to the
+    /// minimal distance are recomputed exactly, as well as the distances returned by [`PointSet::distance`].
+    /// After quantization, the `distance_matrix` field is empty.
+    ///
+    /// With the dense backend, the matrix takes 2 bytes (`U16`) or 1 byte (`U8`) per pair of points instead
+    /// of 8, and the sorted neighbour lists are truncated to [`QUANTIZED_NB_NEIGHBOURS`] neighbours by default
+    /// (see [`PointSet::with_nb_neighbours`]) instead of taking 8 bytes per pair as well: 20000 points take
+    /// 0.8 GB (`U16`) or 0.4 GB (`U8`) instead of 6.4 GB. Other backends do not store any matrix: the
+    /// quantization is applied when the PointSet is rebuilt with the dense backend (see [`PointSet::with_backend`]),
+    /// whose distances are then coded as soon as they are computed, without ever storing the exact matrix.
+    /// A dense PointSet already storing its exact matrix is converted row by row.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points = wsp::PointSet::init_from_random(1000, 10, 51);
+    /// // Only the quantized matrix is computed
+    /// let mut quantized = wsp::PointSet::init_from_preset_with_backend(
+    ///     points.points.clone(),
+    ///     wsp::Metric::Manhattan,
+    ///     wsp::Backend::KdTree,
+    /// )
+    /// .with_quantization(wsp::Quantization::U16)
+    /// .with_backend(wsp::Backend::Dense);
+    /// let mut exact = points;
+    /// wsp::wsp(&mut quantized, 1.0);
+    /// wsp::wsp(&mut exact, 1.0);
+    /// assert_eq!(quantized.active, exact.active);
+    /// ```
+    pub fn with_quantization(mut self, quantization: Quantization) -> PointSet {
+        if self.quantization == Some(quantization) {
+            return self;
+        }
+        self.quantization = Some(quantization);
+        if self.backend != Backend::Dense {
+            return self;
+        }
+        if self.quantized.is_some() {
+            // Recompute the distances with the new precision
+            let summation = self.summation;
+            return self.rebuild(Backend::Dense, summation);
+        }
+        let distance_matrix = std::mem::take(&mut self.distance_matrix);
+        self.quantized = Some(QuantizedMatrix::from_matrix(
+            distance_matrix,
+            self.d_max,
+            quantization,
+        ));
+        // Truncated neighbour lists, unless set by the user
+        if self.nb_neighbours.is_none() {
+            self.idx_sort = Vec::new();
+        }
+        self
+    }
+
+    /// Returns the precision of the quantized distance matrix, if any.
+    pub fn quantization(&self) -> Option<Quantization> {
+        self.quantization
+    }
+
+    /// Number of neighbours kept in each sorted list of the dense backend, if truncated.
+    pub(crate) fn neighbour_list_len(&self) -> Option<usize> {
+        self.nb_neighbours
+            .or(self.quantized.as_ref().map(|_| QUANTIZED_NB_NEIGHBOURS))
+    }
+
+    /// Whether the distance between points `i` and `j` is strictly lower than `threshold`. Dense backend only.
+    pub(crate) fn is_closer(&self, i: usize, j: usize, threshold: f64) -> bool {
+        match self.quantized.as_ref() {
+            None => self.distance_matrix[i][j] < threshold,
+            Some(quantized) => quantized
+                .is_closer(i, j, threshold)
+                .unwrap_or_else(|| self.exact_distance(i, j) < threshold),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{adaptive_wsp, wsp, Metric};
+
+    #[test]
+    fn test_quantized_same_result() {
+        let points = PointSet::init_from_random(500, 5, 51).points;
+        for quantization in [Quantization::U16, Quantization::U8] {
+            for metric in [Metric::Manhattan, Metric::Euclidean] {
+                let mut exact =
+                    PointSet::init_from_preset_with_backend(points.clone(), metric, Backend::Dense);
+                let mut quantized =
+                    PointSet::init_from_preset_with_backend(points.clone(), metric, Backend::Dense)
+                        .with_quantization(quantization);
+                assert!(quantized.distance_matrix.is_empty());
+                assert_eq!(quantized.distance(3, 7), exact.distance(3, 7));
+                wsp(&mut exact, 0.8);
+                wsp(&mut quantized, 0.8);
+                assert_eq!(quantized.active, exact.active);
+
+                let mut exact =
+                    PointSet::init_from_preset_with_backend(points.clone(), metric, Backend::Dense);
+                let mut quantized =
+                    PointSet::init_from_preset_with_backend(points.clone(), metric, Backend::Dense)
+                        .with_quantization(quantization);
+                adaptive_wsp(&mut exact, 50, false);
+                adaptive_wsp(&mut quantized, 50, false);
+                assert_eq!(quantized.active, exact.active);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantized_footprint() {
+        let points = PointSet::init_from_random(300, 4, 51).points;
+        let mut exact = PointSet::init_from_preset_with_backend(
+            points.clone(),
+            Metric::Euclidean,
+            Backend::Dense,
+        );
+        // Built from the matrix-free backend: the exact matrix is never computed
+        let mut quantized =
+            PointSet::init_from_preset_with_backend(points, Metric::Euclidean, Backend::KdTree)
+                .with_quantization(Quantization::U8)
+                .with_backend(Backend::Dense);
+        assert!(quantized.distance_matrix.is_empty());
+        assert!(matches!(
+            quantized.quantized.as_ref().map(|q| &q.codes),
+            Some(Codes::U8(codes)) if codes.len() == 300 * 300
+        ));
+        assert_eq!(
+            (quantized.d_min, quantized.d_max),
+            (exact.d_min, exact.d_max)
+        );
+
+        // One byte per pair of points, and truncated neighbour lists
+        quantized.compute_closest_idx();
+        assert!(quantized
+            .idx_sort
+            .iter()
+            .all(|idxs| idxs.len() == QUANTIZED_NB_NEIGHBOURS + 1));
+        wsp(&mut exact, 0.3);
+        wsp(&mut quantized, 0.3);
+        assert_eq!(quantized.active, exact.active);
+
+        let quantized = quantized.with_quantization(Quantization::U16);
+        assert!(matches!(
+            quantized.quantized.as_ref().map(|q| &q.codes),
+            Some(Codes::U16(_))
+        ));
+        assert_eq!(quantized.active, exact.active);
+    }
+}
@@ -0,0 +1,84 @@
+//! Ordering of the points of a design such that any prefix of the ordering is
+//! itself (approximately) space-filling.
+
+use crate::PointSet;
+
+/// Orders the active points of the PointSet using a greedy maximin strategy.
+/// The first point is the most central active point. Each following point is the active point
+/// maximizing its distance to the closest already ranked point.
+/// Any prefix of the result is thus an approximately space-filling design, letting users
+/// truncate the design later without running the selection again.
+///
+/// Returns the indexes of the active points, from the most to the least important.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+/// wsp::wsp(&mut points, 2.0);
+/// let ranking = wsp::rank_points(&points);
+/// assert_eq!(ranking.len(), points.nb_active);
+///
+/// // Keep only the 10 most important points
+/// let design: Vec<&Vec<f64>> = ranking.iter().take(10).map(|&i| &points.points[i]).collect();
+/// ```
+pub fn rank_points(set: &PointSet) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..set.points.len()).filter(|&i| set.active[i]).collect();
+    let mut ranking: Vec<usize> = Vec::with_capacity(remaining.len());
+    if remaining.is_empty() {
+        return ranking;
+    }
+
+    // Start with the most central point
+    let first = remaining
+        .iter()
+        .enumerate()
+        .map(|(pos, &i)| {
+            let sum: f64 = remaining.iter().map(|&j| set.distance(i, j)).sum();
+            (pos, sum)
+        })
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap()
+        .0;
+    let first = remaining.swap_remove(first);
+    ranking.push(first);
+
+    // Distance of each remaining point to the closest ranked point
+    let mut closest: Vec<f64> = remaining.iter().map(|&i| set.distance(i, first)).collect();
+
+    while !remaining.is_empty() {
+        let next = closest
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .unwrap()
+            .0;
+        let chosen = remaining.swap_remove(next);
+        closest.swap_remove(next);
+        ranking.push(chosen);
+
+        for (d, &i) in closest.iter_mut().zip(remaining.iter()) {
+            *d = d.min(set.distance(i, chosen));
+        }
+    }
+    ranking
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_points() {
+        let p1 = vec![0.0, 0.0];
+        let p2 = vec![1.2, 0.0];
+        let p3 = vec![0.5, 0.0];
+        let p4 = vec![0.9, 0.0];
+        let mut pointset = PointSet::init_from_preset(vec![p1, p2, p3, p4]);
+        pointset.active.set(3, false);
+        pointset.nb_active -= 1;
+
+        // Central point first, then the farthest points from the ranked points
+        assert_eq!(rank_points(&pointset), vec![2, 1, 0]);
+    }
+}
@@ -0,0 +1,158 @@
+//! Compact binary format for sets of points: a header with the number of rows and columns
+//! (two little-endian u64), followed by the coordinates as row-major little-endian f64.
+//! Reading and writing it is much faster than CSV for large candidate sets.
+//! With NumPy, such a file is written with `np.array(a.shape, '<u8').tofile(f); a.astype('<f8').tofile(f)`.
+
+use crate::binary::{read_usize, write_f64, write_u64};
+use crate::{PointSet, WspError};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+/// Size in bytes of the header of the format.
+const HEADER_SIZE: u64 = 16;
+
+/// Reads a set of points stored in the raw binary format.
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the binary file.
+///
+/// # Example
+///
+/// ```
+/// let points = wsp::PointSet::init_from_random(100, 10, 51);
+/// points.save_in_binary("wsp_points.bin", false).unwrap();
+///
+/// let read = wsp::read_points_binary("wsp_points.bin").unwrap();
+/// assert_eq!(read, points.points);
+/// # std::fs::remove_file("wsp_points.bin").unwrap();
+/// ```
+pub fn read_points_binary(filepath: &str) -> Result<Vec<Vec<f64>>, Box<dyn Error>> {
+    let file = File::open(filepath)?;
+    let file_size = file.metadata()?.len();
+    let mut rdr = BufReader::new(file);
+
+    let nb_points = read_usize(&mut rdr)?;
+    let nb_dim = read_usize(&mut rdr)?;
+    let expected_size = (nb_points as u64)
+        .checked_mul(nb_dim as u64)
+        .and_then(|n| n.checked_mul(8))
+        .and_then(|n| n.checked_add(HEADER_SIZE));
+    if expected_size != Some(file_size) {
+        return Err(WspError::InvalidFile(format!(
+            "expected {} points of dimension {}, but the file contains {} bytes",
+            nb_points, nb_dim, file_size
+        ))
+        .into());
+    }
+
+    let mut buf = vec![0u8; nb_dim * 8];
+    let mut points = Vec::with_capacity(nb_points);
+    for _ in 0..nb_points {
+        rdr.read_exact(&mut buf)?;
+        points.push(
+            buf.chunks_exact(8)
+                .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+                .collect(),
+        );
+    }
+    Ok(points)
+}
+
+/// Stores points in the raw binary format, one point per row (or per column if `transpose` is set).
+/// See [`PointSet::save_in_binary`] to store the active points of a PointSet.
+///
+/// # Arguments
+///
+/// * `points` - The points to store.
+/// * `filepath` - The path to the file where to store the points.
+/// * `transpose` - Transpose the matrix in the output file
+pub fn write_points_binary(
+    points: &[Vec<f64>],
+    filepath: &str,
+    transpose: bool,
+) -> Result<(), Box<dyn Error>> {
+    write_rows(points.iter(), points.len(), filepath, transpose)
+}
+
+fn write_rows<'a, I>(
+    points: I,
+    nb_points: usize,
+    filepath: &str,
+    transpose: bool,
+) -> Result<(), Box<dyn Error>>
+where
+    I: Iterator<Item = &'a Vec<f64>> + Clone,
+{
+    let mut wrt = BufWriter::new(File::create(filepath)?);
+    let nb_dim = points.clone().next().map_or(0, |p| p.len());
+
+    if transpose {
+        write_u64(&mut wrt, nb_dim as u64)?;
+        write_u64(&mut wrt, nb_points as u64)?;
+        for d in 0..nb_dim {
+            for point in points.clone() {
+                write_f64(&mut wrt, point[d])?;
+            }
+        }
+    } else {
+        write_u64(&mut wrt, nb_points as u64)?;
+        write_u64(&mut wrt, nb_dim as u64)?;
+        for point in points {
+            for &x in point.iter() {
+                write_f64(&mut wrt, x)?;
+            }
+        }
+    }
+    wrt.flush()?;
+    Ok(())
+}
+
+impl PointSet {
+    /// Stores the active points of the PointSet in the raw binary format. See [`read_points_binary`].
+    ///
+    /// # Arguments
+    ///
+    /// * `filepath` - The path to the file where to store the PointSet points.
+    /// * `transpose` - Transpose the matrix in the output file
+    pub fn save_in_binary(&self, filepath: &str, transpose: bool) -> Result<(), Box<dyn Error>> {
+        write_rows(self.iter_remaining(), self.nb_active, filepath, transpose)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsp;
+
+    #[test]
+    fn test_binary_round_trip() {
+        let filepath = "wsp_test_raw.bin";
+        let mut points = PointSet::init_from_random(200, 3, 51);
+        wsp(&mut points, 0.3);
+        points.save_in_binary(filepath, false).unwrap();
+        assert_eq!(
+            read_points_binary(filepath).unwrap(),
+            points.get_remaining()
+        );
+
+        points.save_in_binary(filepath, true).unwrap();
+        let transposed = read_points_binary(filepath).unwrap();
+        assert_eq!(transposed.len(), 3);
+        assert_eq!(transposed[2][1], points.get_remaining()[1][2]);
+        std::fs::remove_file(filepath).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let filepath = "wsp_test_raw_truncated.bin";
+        let mut content = Vec::new();
+        write_u64(&mut content, 10).unwrap();
+        write_u64(&mut content, 2).unwrap();
+        write_f64(&mut content, 1.0).unwrap();
+        std::fs::write(filepath, content).unwrap();
+        assert!(read_points_binary(filepath).is_err());
+        std::fs::remove_file(filepath).unwrap();
+    }
+}
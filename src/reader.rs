@@ -0,0 +1,174 @@
+//! Streaming reader of candidate points stored in CSV files (one point per row, without header).
+//! Rows are parsed one by one in a reused buffer and returned in chunks, so that the memory
+//! used for parsing does not depend on the size of the file.
+
+use crate::WspError;
+use csv::{ByteRecord, Reader, ReaderBuilder};
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+
+/// Iterator over the points of a CSV file, by chunks of at most a given number of points.
+/// The dimension of all points is checked against the dimension of the first point.
+/// Empty fields are read as missing values (NaN), see [`crate::MissingValues`].
+///
+/// # Example
+///
+/// ```
+/// let points = wsp::PointSet::init_from_random(100, 10, 51);
+/// points.save_in_csv("wsp_chunks.csv", false).unwrap();
+///
+/// let mut nb_points = 0;
+/// for chunk in wsp::CsvChunks::from_path("wsp_chunks.csv", 32).unwrap() {
+///     let chunk = chunk.unwrap();
+///     assert!(chunk.len() <= 32);
+///     nb_points += chunk.len();
+/// }
+/// assert_eq!(nb_points, 100);
+/// # std::fs::remove_file("wsp_chunks.csv").unwrap();
+/// ```
+pub struct CsvChunks<R: Read> {
+    rdr: Reader<R>,
+    record: ByteRecord,
+    chunk_size: usize,
+    nb_dim: Option<usize>,
+    nb_read: usize,
+    done: bool,
+}
+
+impl CsvChunks<File> {
+    /// Opens the CSV file `filepath`, to read it by chunks of `chunk_size` points.
+    pub fn from_path(filepath: &str, chunk_size: usize) -> Result<CsvChunks<File>, Box<dyn Error>> {
+        Ok(CsvChunks::from_reader(File::open(filepath)?, chunk_size))
+    }
+}
+
+impl<R: Read> CsvChunks<R> {
+    /// Reads the CSV content of `rdr` by chunks of `chunk_size` points.
+    pub fn from_reader(rdr: R, chunk_size: usize) -> CsvChunks<R> {
+        assert!(chunk_size > 0, "The chunk size must be positive");
+        CsvChunks {
+            rdr: ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(rdr),
+            record: ByteRecord::new(),
+            chunk_size,
+            nb_dim: None,
+            nb_read: 0,
+            done: false,
+        }
+    }
+
+    /// Parses the current record as a point.
+    fn parse_record(&self) -> Result<Vec<f64>, Box<dyn Error>> {
+        let nb_dim = self.nb_dim.unwrap_or(self.record.len());
+        if self.record.len() != nb_dim {
+            return Err(WspError::DimensionMismatch {
+                expected: nb_dim,
+                found: self.record.len(),
+            }
+            .into());
+        }
+        self.record
+            .iter()
+            .enumerate()
+            .map(|(dim, field)| {
+                let field = std::str::from_utf8(field).unwrap_or("?").trim();
+                if field.is_empty() {
+                    return Ok(f64::NAN);
+                }
+                field.parse::<f64>().map_err(|_| {
+                    WspError::InvalidFile(format!(
+                        "coordinate {} of point #{} is not a number: {}",
+                        dim, self.nb_read, field
+                    ))
+                    .into()
+                })
+            })
+            .collect()
+    }
+
+    fn read_chunk(&mut self) -> Result<Vec<Vec<f64>>, Box<dyn Error>> {
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        while chunk.len() < self.chunk_size {
+            if !self.rdr.read_byte_record(&mut self.record)? {
+                self.done = true;
+                break;
+            }
+            let point = self.parse_record()?;
+            self.nb_dim = Some(point.len());
+            self.nb_read += 1;
+            chunk.push(point);
+        }
+        Ok(chunk)
+    }
+}
+
+impl<R: Read> Iterator for CsvChunks<R> {
+    type Item = Result<Vec<Vec<f64>>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_chunk() {
+            Ok(chunk) if chunk.is_empty() => None,
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Reads all the points of a CSV file (one point per row, without header), as written by
+/// [`crate::PointSet::save_in_csv`]. The file is streamed, see [`CsvChunks`].
+///
+/// # Arguments
+///
+/// * `filepath` - The path to the CSV file.
+pub fn read_points_csv(filepath: &str) -> Result<Vec<Vec<f64>>, Box<dyn Error>> {
+    let mut points = Vec::new();
+    for chunk in CsvChunks::from_path(filepath, 4096)? {
+        points.extend(chunk?);
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks() {
+        let content = "0.0,1.0\n2.0,3.0\n4.0,\n6.0,7.0\n8.0,9.0\n";
+        let chunks: Vec<Vec<Vec<f64>>> = CsvChunks::from_reader(content.as_bytes(), 2)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], vec![vec![0.0, 1.0], vec![2.0, 3.0]]);
+        assert!(chunks[1][0][1].is_nan());
+        assert_eq!(chunks[2], vec![vec![8.0, 9.0]]);
+    }
+
+    #[test]
+    fn test_invalid_rows() {
+        let content = "0.0,1.0\n2.0,3.0,4.0\n";
+        let mut chunks = CsvChunks::from_reader(content.as_bytes(), 10);
+        let err = chunks.next().unwrap().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<WspError>(),
+            Some(&WspError::DimensionMismatch {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert!(chunks.next().is_none());
+
+        let content = "0.0,1.0\n2.0,abc\n";
+        let mut chunks = CsvChunks::from_reader(content.as_bytes(), 10);
+        assert!(chunks.next().unwrap().is_err());
+    }
+}
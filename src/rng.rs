@@ -0,0 +1,208 @@
+//! Choice of the random number generator. `SmallRng` is fast, but its algorithm is not specified and
+//! may differ between platforms and versions of `rand`. Audited experimental designs may require a
+//! documented generator instead, whose output stream is fixed by its specification.
+//! All the random streams of a run are seeded from a single seed, with a documented splitting scheme.
+
+use rand::rngs::SmallRng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rand_pcg::Pcg64;
+use std::fmt;
+use std::str::FromStr;
+
+/// Algorithm of the random number generators seeded by the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RngAlgorithm {
+    /// `rand::rngs::SmallRng`. Fastest, but not portable across platforms and versions of `rand`.
+    #[default]
+    Small,
+    /// ChaCha stream cipher with 20 rounds (`rand_chacha::ChaCha20Rng`). Portable and cryptographically strong.
+    ChaCha20,
+    /// PCG XSL RR 128/64 (`rand_pcg::Pcg64`). Portable, and faster than ChaCha20.
+    Pcg64,
+}
+
+impl RngAlgorithm {
+    /// Returns a generator of the algorithm, seeded with `seed`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rand::Rng;
+    ///
+    /// let mut rng = wsp::RngAlgorithm::Pcg64.seeded(51);
+    /// let mut points = wsp::PointSet::init_from_random_with_rng(1000, 2, &mut rng);
+    /// let x: f64 = rng.gen();
+    /// ```
+    pub fn seeded(self, seed: u64) -> SeededRng {
+        match self {
+            RngAlgorithm::Small => SeededRng::Small(SmallRng::seed_from_u64(seed)),
+            RngAlgorithm::ChaCha20 => {
+                SeededRng::ChaCha20(Box::new(ChaCha20Rng::seed_from_u64(seed)))
+            }
+            RngAlgorithm::Pcg64 => SeededRng::Pcg64(Pcg64::seed_from_u64(seed)),
+        }
+    }
+}
+
+impl fmt::Display for RngAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            RngAlgorithm::Small => "small",
+            RngAlgorithm::ChaCha20 => "chacha20",
+            RngAlgorithm::Pcg64 => "pcg64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for RngAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RngAlgorithm, String> {
+        match s {
+            "small" => Ok(RngAlgorithm::Small),
+            "chacha20" => Ok(RngAlgorithm::ChaCha20),
+            "pcg64" => Ok(RngAlgorithm::Pcg64),
+            _ => Err(format!("Unknown random number generator: {}", s)),
+        }
+    }
+}
+
+/// Independent random streams of a run, each seeded from the seed of the run with [`derive_seed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeedStream {
+    /// Generation of the initial candidates.
+    Generation,
+    /// Choice of the origins of the algorithm. Each iteration of the adaptive algorithm and each pass of
+    /// the annealing re-seeds this stream, and thus starts from the same origin, while
+    /// [`crate::wsp_restarts`] draws its successive, different origins from a single generator seeded
+    /// with it.
+    Origin,
+    /// Initialisation of the k-means clustering.
+    Clustering,
+    /// Uniform sub-sampling of the candidate pool.
+    Subsampling,
+}
+
+/// Derives the seed of a random stream from the seed of the run. The seed of the stream of index `k`
+/// (in the order of [`SeedStream`], from 0) is the output number `k + 1` of the SplitMix64 generator
+/// seeded with `seed`, i.e., the SplitMix64 finalizer applied to `seed + (k + 1) * 0x9e3779b97f4a7c15`
+/// with wrapping arithmetic. The seeds of all the streams can thus be recomputed independently.
+///
+/// # Example
+///
+/// ```
+/// use wsp::{derive_seed, SeedStream};
+///
+/// let points = wsp::PointSet::init_from_random(1000, 2, derive_seed(51, SeedStream::Generation));
+/// assert_ne!(derive_seed(51, SeedStream::Generation), derive_seed(51, SeedStream::Origin));
+/// ```
+pub fn derive_seed(seed: u64, stream: SeedStream) -> u64 {
+    let mut z = seed.wrapping_add((stream as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Random number generator of one of the algorithms of [`RngAlgorithm`].
+/// It can be passed to all the `_with_rng` functions of the crate.
+#[derive(Debug, Clone)]
+pub enum SeededRng {
+    Small(SmallRng),
+    /// Boxed, as its state is ten times larger than the others
+    ChaCha20(Box<ChaCha20Rng>),
+    Pcg64(Pcg64),
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SeededRng::Small(rng) => rng.next_u32(),
+            SeededRng::ChaCha20(rng) => rng.next_u32(),
+            SeededRng::Pcg64(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SeededRng::Small(rng) => rng.next_u64(),
+            SeededRng::ChaCha20(rng) => rng.next_u64(),
+            SeededRng::Pcg64(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SeededRng::Small(rng) => rng.fill_bytes(dest),
+            SeededRng::ChaCha20(rng) => rng.fill_bytes(dest),
+            SeededRng::Pcg64(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        match self {
+            SeededRng::Small(rng) => rng.try_fill_bytes(dest),
+            SeededRng::ChaCha20(rng) => rng.try_fill_bytes(dest),
+            SeededRng::Pcg64(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wsp, wsp_with_rng, PointSet};
+
+    #[test]
+    fn test_documented_streams() {
+        // The default algorithm gives the same results as before it was configurable
+        let mut small = RngAlgorithm::Small.seeded(51);
+        assert_eq!(
+            PointSet::init_from_random_with_rng(100, 3, &mut small).points,
+            PointSet::init_from_random(100, 3, 51).points
+        );
+
+        // The streams of the documented generators must never change
+        assert_eq!(
+            RngAlgorithm::ChaCha20.seeded(51).next_u64(),
+            0x5b14_42e6_4884_e8fc
+        );
+        assert_eq!(
+            RngAlgorithm::Pcg64.seeded(51).next_u64(),
+            0x73bb_73d8_01ab_fc9c
+        );
+
+        for algorithm in [RngAlgorithm::ChaCha20, RngAlgorithm::Pcg64] {
+            let mut rng = algorithm.seeded(51);
+            let points = PointSet::init_from_random_with_rng(500, 2, &mut rng).points;
+            assert!(points.iter().flatten().all(|&x| (0.0..1.0).contains(&x)));
+            let mut set = PointSet::init_from_preset(points).with_rng_algorithm(algorithm);
+            wsp(&mut set, 0.1);
+            assert!(set.nb_active > 1 && set.nb_active < 500);
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+    }
+
+    #[test]
+    fn test_derived_seeds() {
+        // First output of SplitMix64 seeded with 0
+        assert_eq!(
+            derive_seed(0, SeedStream::Generation),
+            0xe220_a839_7b1d_cdaf
+        );
+
+        // The origin of a seeded PointSet comes from the origin stream
+        let points = PointSet::init_from_random(1000, 2, derive_seed(51, SeedStream::Generation));
+        let mut seeded = PointSet::init_from_preset(points.points.clone()).with_seed(51);
+        wsp(&mut seeded, 0.05);
+        let mut expected = points;
+        let origin_seed = derive_seed(51, SeedStream::Origin);
+        wsp_with_rng(
+            &mut expected,
+            0.05,
+            &mut SmallRng::seed_from_u64(origin_seed),
+        );
+        assert_eq!(seeded.active, expected.active);
+    }
+}
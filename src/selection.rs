@@ -0,0 +1,272 @@
+//! Criteria to select the best design among the designs computed by the multi-run modes: several
+//! origins ([`wsp_restarts`]), several seeds ([`wsp_seeds`]), and the iterations of the adaptive
+//! algorithm ([`crate::adaptive_wsp_with_selection`]).
+
+use crate::{derive_seed, wsp_with_rng, DMin, PointSet, RunStats, SeedStream, Snapshot};
+use std::fmt;
+use std::str::FromStr;
+
+/// Definition of the best design of a multi-run mode.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SelectionCriterion {
+    /// The design with the most active points.
+    #[default]
+    MaxActive,
+    /// The design with the largest minimal distance between two active points (maximin).
+    MaxMinDistance,
+    /// The design with the smallest Morris-Mitchell criterion `phi_p` (see [`phi_p`]) for the given `p`.
+    /// Large values of `p` (e.g. 50) approach the maximin criterion, while also penalising the number of
+    /// pairs at the minimal distance.
+    MinPhiP(f64),
+}
+
+impl SelectionCriterion {
+    /// Returns the score of the current design of the PointSet. The best design has the largest score.
+    /// The distance criteria compare all the pairs of active points.
+    pub fn score(&self, set: &PointSet) -> f64 {
+        match *self {
+            SelectionCriterion::MaxActive => set.nb_active as f64,
+            SelectionCriterion::MaxMinDistance => min_distance(set),
+            SelectionCriterion::MinPhiP(p) => -phi_p(set, p),
+        }
+    }
+}
+
+impl fmt::Display for SelectionCriterion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectionCriterion::MaxActive => write!(f, "max-active"),
+            SelectionCriterion::MaxMinDistance => write!(f, "max-min-distance"),
+            SelectionCriterion::MinPhiP(p) => write!(f, "min-phi:{}", p),
+        }
+    }
+}
+
+impl FromStr for SelectionCriterion {
+    type Err = String;
+
+    /// Parses `max-active`, `max-min-distance` or `min-phi:<p>`.
+    fn from_str(s: &str) -> Result<SelectionCriterion, String> {
+        match s.split_once(':') {
+            None if s == "max-active" => Ok(SelectionCriterion::MaxActive),
+            None if s == "max-min-distance" => Ok(SelectionCriterion::MaxMinDistance),
+            Some(("min-phi", value)) => match value.parse::<f64>() {
+                Ok(p) if p > 0.0 => Ok(SelectionCriterion::MinPhiP(p)),
+                _ => Err(format!("Invalid exponent \"{}\"", value)),
+            },
+            _ => Err(format!(
+                "Unknown selection criterion \"{}\", expected max-active, max-min-distance or min-phi:<p>",
+                s
+            )),
+        }
+    }
+}
+
+/// Iterates over the distances between all the pairs of active points.
+fn active_distances(set: &PointSet) -> impl Iterator<Item = f64> + '_ {
+    set.active.iter_ones().flat_map(move |i| {
+        std::iter::successors(set.active.next_one(i + 1), move |&j| {
+            set.active.next_one(j + 1)
+        })
+        .map(move |j| set.distance(i, j))
+    })
+}
+
+/// Returns the minimal distance between two active points of the PointSet,
+/// or infinity if less than two points are active.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+/// wsp::wsp(&mut points, 0.05);
+/// assert!(wsp::min_distance(&points) >= 0.05);
+/// ```
+pub fn min_distance(set: &PointSet) -> f64 {
+    active_distances(set).fold(f64::INFINITY, f64::min)
+}
+
+/// Returns the Morris-Mitchell criterion of the active points of the PointSet,
+/// `phi_p = (sum of d^-p over all the pairs of active points)^(1/p)`. Smaller is better.
+/// The sum is scaled by the minimal distance to avoid overflows with large values of `p`.
+/// Returns 0 if less than two points are active.
+pub fn phi_p(set: &PointSet, p: f64) -> f64 {
+    let d_min = min_distance(set);
+    if d_min.is_infinite() {
+        return 0.0;
+    }
+    let sum: f64 = active_distances(set).map(|d| (d_min / d).powf(p)).sum();
+    sum.powf(1.0 / p) / d_min
+}
+
+/// Executes the WSP algorithm `nb_restarts` times from different origins, and keeps the best design
+/// according to `criterion`. The origins are drawn successively from the origin stream of the PointSet,
+/// so the first run is the classical algorithm (see [`crate::wsp`]). On ties, the earliest design is kept.
+/// Returns the statistics accumulated over all the runs.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The desired minimal distance between all remaining points in the PointSet. See [`crate::wsp`].
+/// * `nb_restarts` - The number of runs.
+/// * `criterion` - The definition of the best design.
+///
+/// # Example
+///
+/// ```
+/// use wsp::SelectionCriterion;
+///
+/// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+/// wsp::wsp_restarts(&mut points, 0.05, 5, SelectionCriterion::MaxActive);
+/// let best = points.nb_active;
+///
+/// let mut single = wsp::PointSet::init_from_random(1000, 2, 51);
+/// wsp::wsp(&mut single, 0.05);
+/// assert!(best >= single.nb_active);
+/// ```
+pub fn wsp_restarts(
+    set: &mut PointSet,
+    d_min: impl Into<DMin>,
+    nb_restarts: usize,
+    criterion: SelectionCriterion,
+) -> RunStats {
+    assert!(nb_restarts > 0, "The number of restarts must be positive");
+    let d_min = d_min.into();
+    let mut rng = set.rng_algorithm.seeded(set.origin_seed);
+    let (_, stats) = best_of_runs(set, nb_restarts, criterion, |set, _| {
+        wsp_with_rng(set, d_min, &mut rng)
+    });
+    stats
+}
+
+/// Executes the WSP algorithm once for each seed of `seeds`, and keeps the best design according to
+/// `criterion`. The run of a seed draws its origin as [`PointSet::with_seed`] does, so the best design is
+/// obtained again with a single run seeded with the returned seed. On ties, the earliest seed is kept.
+/// Returns the seed of the best design, and the statistics accumulated over all the runs.
+///
+/// # Arguments
+///
+/// * `set` - The PointSet instance. `set` is mutably borrowed.
+/// * `d_min` - The desired minimal distance between all remaining points in the PointSet. See [`crate::wsp`].
+/// * `seeds` - The seeds of the runs. Must not be empty.
+/// * `criterion` - The definition of the best design.
+///
+/// # Example
+///
+/// ```
+/// use wsp::SelectionCriterion;
+///
+/// let candidates = wsp::PointSet::init_from_random(1000, 2, 51).points;
+/// let mut points = wsp::PointSet::init_from_preset(candidates.clone());
+/// let (seed, _) = wsp::wsp_seeds(&mut points, 0.05, &[1, 2, 3], SelectionCriterion::MaxMinDistance);
+///
+/// let mut single = wsp::PointSet::init_from_preset(candidates).with_seed(seed);
+/// wsp::wsp(&mut single, 0.05);
+/// assert_eq!(single.active, points.active);
+/// ```
+pub fn wsp_seeds(
+    set: &mut PointSet,
+    d_min: impl Into<DMin>,
+    seeds: &[u64],
+    criterion: SelectionCriterion,
+) -> (u64, RunStats) {
+    assert!(!seeds.is_empty(), "At least one seed is required");
+    let d_min = d_min.into();
+    let algorithm = set.rng_algorithm;
+    let (best, stats) = best_of_runs(set, seeds.len(), criterion, |set, run| {
+        let mut rng = algorithm.seeded(derive_seed(seeds[run], SeedStream::Origin));
+        wsp_with_rng(set, d_min, &mut rng)
+    });
+    (seeds[best], stats)
+}
+
+/// Executes `run` `nb_runs` times, each time from the initial state of the PointSet, and restores the
+/// best design. Returns the index of the best run, and the statistics accumulated over all the runs.
+fn best_of_runs<F>(
+    set: &mut PointSet,
+    nb_runs: usize,
+    criterion: SelectionCriterion,
+    mut run: F,
+) -> (usize, RunStats)
+where
+    F: FnMut(&mut PointSet, usize) -> RunStats,
+{
+    let initial = set.snapshot();
+    let mut stats = RunStats::default();
+    let mut best: Option<(f64, usize, Snapshot)> = None;
+    for k in 0..nb_runs {
+        if k > 0 {
+            set.restore(&initial);
+        }
+        stats += run(set, k);
+        let score = criterion.score(set);
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _, _)| score > *best_score)
+        {
+            best = Some((score, k, set.snapshot()));
+        }
+    }
+    let (_, best_run, design) = best.expect("At least one run");
+    set.restore(&design);
+    (best_run, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{adaptive_wsp, adaptive_wsp_with_selection, wsp};
+
+    #[test]
+    fn test_selection() {
+        let candidates = PointSet::init_from_random(600, 2, 51).points;
+        let criteria = [
+            SelectionCriterion::MaxActive,
+            SelectionCriterion::MaxMinDistance,
+            SelectionCriterion::MinPhiP(20.0),
+        ];
+        for criterion in criteria {
+            assert_eq!(criterion.to_string().parse(), Ok(criterion));
+
+            // The best of several restarts is at least as good as each of the runs
+            let mut best = PointSet::init_from_preset(candidates.clone());
+            wsp_restarts(&mut best, 0.05, 4, criterion);
+            let mut single = PointSet::init_from_preset(candidates.clone());
+            wsp(&mut single, 0.05);
+            assert!(criterion.score(&best) >= criterion.score(&single));
+            assert!(min_distance(&best) >= 0.05);
+
+            let mut seeded = PointSet::init_from_preset(candidates.clone());
+            let (seed, stats) = wsp_seeds(&mut seeded, 0.05, &[7, 8, 9], criterion);
+            assert_eq!(stats.nb_runs, 3);
+            let mut again = PointSet::init_from_preset(candidates.clone()).with_seed(seed);
+            wsp(&mut again, 0.05);
+            assert_eq!(again.active, seeded.active);
+
+            // The criterion only breaks ties between the iterations equally close to the target
+            let mut adaptive = PointSet::init_from_preset(candidates.clone());
+            adaptive_wsp_with_selection(&mut adaptive, 40, false, criterion);
+            let mut reference = PointSet::init_from_preset(candidates.clone());
+            adaptive_wsp(&mut reference, 40, false);
+            assert_eq!(
+                adaptive.nb_active.abs_diff(40),
+                reference.nb_active.abs_diff(40)
+            );
+        }
+        assert!("min-phi:0".parse::<SelectionCriterion>().is_err());
+        assert!("max".parse::<SelectionCriterion>().is_err());
+    }
+
+    #[test]
+    fn test_phi_p() {
+        let mut set = PointSet::init_from_preset(vec![vec![0.0], vec![1.0], vec![3.0]]);
+        assert_eq!(min_distance(&set), 1.0);
+        // (1 + 1/2^2 + 1/3^2)^(1/2)
+        assert!((phi_p(&set, 2.0) - (1.0f64 + 0.25 + 1.0 / 9.0).sqrt()).abs() < 1e-12);
+        set.active.set(1, false);
+        set.active.set(2, false);
+        set.nb_active = 1;
+        assert_eq!(min_distance(&set), f64::INFINITY);
+        assert_eq!(phi_p(&set, 2.0), 0.0);
+    }
+}
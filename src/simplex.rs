@@ -0,0 +1,141 @@
+//! Mixture designs, where points live on the unit simplex: all coordinates
+//! are non-negative and sum to 1.
+
+use crate::{PointSet, WspError};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/// Tolerance used to decide whether a point lies on the simplex.
+const SIMPLEX_TOLERANCE: f64 = 1e-9;
+
+impl PointSet {
+    /// Creates a 'PointSet' using a random initialisation of the points following a uniform distribution
+    /// on the unit simplex.
+    ///
+    /// # Arguments
+    ///
+    /// * `nb_points` - The number of points in the set before running WSP.
+    /// * `nb_dim` - The dimension of the points, i.e., the number of components of the mixture.
+    /// * `seed` - The seed used for the uniform sampling of the coordinates of the points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let pointset = wsp::PointSet::init_from_random_simplex(100, 3, 51);
+    /// for point in pointset.get_remaining() {
+    ///     assert!(wsp::is_on_simplex(&point));
+    /// }
+    /// ```
+    pub fn init_from_random_simplex(nb_points: usize, nb_dim: usize, seed: u64) -> PointSet {
+        PointSet::init_from_random_simplex_with_rng(
+            nb_points,
+            nb_dim,
+            &mut SmallRng::seed_from_u64(seed),
+        )
+    }
+
+    /// Same as [`PointSet::init_from_random_simplex`], drawing the points from a caller-supplied
+    /// random number generator.
+    pub fn init_from_random_simplex_with_rng<R: Rng + ?Sized>(
+        nb_points: usize,
+        nb_dim: usize,
+        rng: &mut R,
+    ) -> PointSet {
+        let mut points: Vec<Vec<f64>> = Vec::with_capacity(nb_points);
+
+        // Normalized exponential samples follow a flat Dirichlet distribution,
+        // i.e., a uniform distribution on the simplex
+        for _ in 0..nb_points {
+            let mut point: Vec<f64> = (0..nb_dim)
+                .map(|_| -(1.0 - rng.gen::<f64>()).ln())
+                .collect();
+            let sum: f64 = point.iter().sum();
+            point.iter_mut().for_each(|x| *x /= sum);
+            points.push(point);
+        }
+
+        PointSet::init_from_preset(points)
+    }
+
+    /// Creates a 'PointSet' from points that must lie on the unit simplex.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The pre-initialised set of points.
+    /// * `project` - If true, each point is replaced by its (euclidian) projection on the simplex.
+    ///   Otherwise, the points are only validated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points: Vec<Vec<f64>> = vec![vec![0.5, 0.5, 0.5], vec![1.0, -0.2, 0.2]];
+    /// assert!(wsp::PointSet::init_from_preset_simplex(points.clone(), false).is_err());
+    /// assert!(wsp::PointSet::init_from_preset_simplex(points, true).is_ok());
+    /// ```
+    pub fn init_from_preset_simplex(
+        mut points: Vec<Vec<f64>>,
+        project: bool,
+    ) -> Result<PointSet, WspError> {
+        for (i, point) in points.iter_mut().enumerate() {
+            if project {
+                *point = project_onto_simplex(point);
+            } else if !is_on_simplex(point) {
+                return Err(WspError::NotOnSimplex(i));
+            }
+        }
+        Ok(PointSet::init_from_preset(points))
+    }
+}
+
+/// Returns true if all coordinates of the point are non-negative and sum to 1.
+pub fn is_on_simplex(point: &[f64]) -> bool {
+    point.iter().all(|&x| x >= -SIMPLEX_TOLERANCE)
+        && (point.iter().sum::<f64>() - 1.0).abs() <= SIMPLEX_TOLERANCE
+}
+
+/// Returns the closest point (for the euclidian distance) on the unit simplex.
+/// Uses the sort-based algorithm from Duchi _et al._ (2008).
+///
+/// # Example
+///
+/// ```
+/// let projected = wsp::project_onto_simplex(&[1.0, 1.0]);
+/// assert_eq!(projected, vec![0.5, 0.5]);
+/// ```
+pub fn project_onto_simplex(point: &[f64]) -> Vec<f64> {
+    let mut sorted = point.to_vec();
+    sorted.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+    let mut cumsum = 0.0;
+    let mut theta = 0.0;
+    for (i, &x) in sorted.iter().enumerate() {
+        cumsum += x;
+        let candidate = (cumsum - 1.0) / (i + 1) as f64;
+        if x - candidate > 0.0 {
+            theta = candidate;
+        }
+    }
+
+    point.iter().map(|&x| (x - theta).max(0.0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_onto_simplex() {
+        assert_eq!(project_onto_simplex(&[0.2, 0.3, 0.5]), vec![0.2, 0.3, 0.5]);
+        assert_eq!(project_onto_simplex(&[2.0, 0.0, 0.0]), vec![1.0, 0.0, 0.0]);
+        assert_eq!(
+            project_onto_simplex(&[1.0, 0.5, -1.0]),
+            vec![0.75, 0.25, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_random_simplex() {
+        let points = PointSet::init_from_random_simplex(500, 4, 51);
+        assert!(points.points.iter().all(|p| is_on_simplex(p)));
+    }
+}
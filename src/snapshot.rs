@@ -0,0 +1,99 @@
+//! Snapshots of the state of the points, to explore several minimal distances or manual edits
+//! of a design and roll back without recomputing the distances or the neighbour index.
+
+use crate::{Backend, BitSet, PointSet};
+
+/// State of the points of a PointSet (active, visited and fixed flags), stored with one bit per flag.
+/// Created with [`PointSet::snapshot`] and restored with [`PointSet::restore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub(crate) active: BitSet,
+    pub(crate) visited: BitSet,
+    pub(crate) fixed: BitSet,
+}
+
+impl Snapshot {
+    /// Returns the number of active points in the snapshot.
+    pub fn nb_active(&self) -> usize {
+        self.active.count_ones()
+    }
+}
+
+impl PointSet {
+    /// Captures the state of the points: which points are active, visited by the algorithm, and fixed.
+    /// The snapshot only takes 3 bits per point.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+    /// let initial = points.snapshot();
+    /// wsp::wsp(&mut points, 1.0);
+    /// let design = points.snapshot();
+    ///
+    /// points.restore(&initial);
+    /// wsp::wsp(&mut points, 1.2);
+    /// assert!(points.nb_active < design.nb_active());
+    ///
+    /// points.restore(&design);
+    /// assert_eq!(points.nb_active, design.nb_active());
+    /// ```
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            active: self.active.clone(),
+            visited: self.visited.clone(),
+            fixed: self.fixed.iter().copied().collect(),
+        }
+    }
+
+    /// Restores the state of the points captured by [`PointSet::snapshot`]. The distances and the
+    /// neighbour index are kept. The snapshot must come from a PointSet with the same number of points.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        assert_eq!(
+            snapshot.active.len(),
+            self.points.len(),
+            "The snapshot does not match the number of points"
+        );
+        self.active.clone_from(&snapshot.active);
+        self.visited.clone_from(&snapshot.visited);
+        self.fixed = snapshot.fixed.to_vec();
+        self.nb_active = snapshot.nb_active();
+        // The positions in the sorted neighbours only speed up the search: scan them again
+        if self.backend == Backend::Dense {
+            self.idx_active = vec![1; self.points.len()];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wsp;
+
+    #[test]
+    fn test_restore() {
+        for backend in [Backend::Dense, Backend::KdTree, Backend::Grid] {
+            let points = PointSet::init_from_random(500, 3, 51).points;
+            let mut set = PointSet::init_from_preset(points.clone()).with_backend(backend);
+            set.set_fixed(&[7]);
+            let initial = set.snapshot();
+            wsp(&mut set, 0.3);
+            let design = set.active.clone();
+
+            set.restore(&initial);
+            assert_eq!(set.nb_active, 500);
+            wsp(&mut set, 0.2);
+            set.restore(&initial);
+            set.clear_fixed();
+            set.restore(&initial);
+            wsp(&mut set, 0.3);
+            assert_eq!(set.active, design);
+            assert!(set.active[7]);
+
+            let mut fresh = PointSet::init_from_preset(points).with_backend(backend);
+            fresh.set_fixed(&[7]);
+            wsp(&mut fresh, 0.3);
+            assert_eq!(fresh.active, design);
+        }
+    }
+}
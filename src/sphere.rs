@@ -0,0 +1,140 @@
+//! Designs on the surface of the unit hypersphere, e.g., direction vectors.
+//! Points are compared with the geodesic (great-circle) distance.
+
+use crate::{Metric, PointSet, Summation, WspError};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+impl PointSet {
+    /// Creates a 'PointSet' using a random initialisation of the points following a uniform distribution
+    /// on the surface of the unit hypersphere. The PointSet uses the geodesic distance.
+    ///
+    /// # Arguments
+    ///
+    /// * `nb_points` - The number of points in the set before running WSP.
+    /// * `nb_dim` - The dimension of the space containing the hypersphere.
+    /// * `seed` - The seed used for the sampling of the coordinates of the points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut pointset = wsp::PointSet::init_from_random_sphere(1000, 3, 51);
+    /// wsp::wsp(&mut pointset, 0.3); // Minimal angle between two directions, in radians
+    /// ```
+    pub fn init_from_random_sphere(nb_points: usize, nb_dim: usize, seed: u64) -> PointSet {
+        PointSet::init_from_random_sphere_with_rng(
+            nb_points,
+            nb_dim,
+            &mut SmallRng::seed_from_u64(seed),
+        )
+    }
+
+    /// Same as [`PointSet::init_from_random_sphere`], drawing the points from a caller-supplied
+    /// random number generator.
+    pub fn init_from_random_sphere_with_rng<R: Rng + ?Sized>(
+        nb_points: usize,
+        nb_dim: usize,
+        rng: &mut R,
+    ) -> PointSet {
+        let mut points: Vec<Vec<f64>> = Vec::with_capacity(nb_points);
+
+        // Normalized gaussian vectors are uniformly distributed on the hypersphere
+        for _ in 0..nb_points {
+            let point: Vec<f64> = loop {
+                let point: Vec<f64> = (0..nb_dim).map(|_| gaussian(rng)).collect();
+                if let Some(point) = normalize(&point) {
+                    break point;
+                }
+            };
+            points.push(point);
+        }
+
+        PointSet::init_from_preset_with_metric(points, Metric::Geodesic)
+    }
+
+    /// Creates a 'PointSet' from already initialised direction vectors.
+    /// Each point is normalized to lie on the unit hypersphere, and the PointSet uses the geodesic distance.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The pre-initialised set of points. They must not be the null vector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points: Vec<Vec<f64>> = vec![vec![2.0, 0.0], vec![0.0, 0.5]];
+    /// let pointset = wsp::PointSet::init_from_preset_sphere(points).unwrap();
+    /// assert_eq!(pointset.points[0], vec![1.0, 0.0]);
+    /// ```
+    pub fn init_from_preset_sphere(points: Vec<Vec<f64>>) -> Result<PointSet, WspError> {
+        let points = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| normalize(point).ok_or(WspError::NotOnSphere(i)))
+            .collect::<Result<Vec<Vec<f64>>, WspError>>()?;
+        Ok(PointSet::init_from_preset_with_metric(
+            points,
+            Metric::Geodesic,
+        ))
+    }
+}
+
+/// Angle, in radians, between the two points seen as vectors.
+#[cfg(test)]
+fn geodesic_distance(p1: &[f64], p2: &[f64]) -> f64 {
+    geodesic_distance_with(p1, p2, Summation::Naive)
+}
+
+/// Angle, in radians, between the two points seen as vectors, with the given summation algorithm.
+pub(crate) fn geodesic_distance_with(p1: &[f64], p2: &[f64], summation: Summation) -> f64 {
+    let dot = summation.sum(p1.iter().zip(p2.iter()).map(|(x1, x2)| x1 * x2));
+    let norms = summation.sum(p1.iter().map(|x| x * x)).sqrt()
+        * summation.sum(p2.iter().map(|x| x * x)).sqrt();
+    // Rounding errors may lead to values slightly outside of [-1, 1]
+    (dot / norms).clamp(-1.0, 1.0).acos()
+}
+
+fn norm(point: &[f64]) -> f64 {
+    point.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+fn normalize(point: &[f64]) -> Option<Vec<f64>> {
+    let norm = norm(point);
+    if norm > 0.0 && norm.is_finite() {
+        Some(point.iter().map(|x| x / norm).collect())
+    } else {
+        None
+    }
+}
+
+/// Standard normal sample using the Box-Muller transform.
+fn gaussian<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>(); // In ]0, 1] to avoid ln(0)
+    let u2: f64 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_geodesic_distance() {
+        let p1 = vec![1.0, 0.0, 0.0];
+        let p2 = vec![0.0, 1.0, 0.0];
+        let p3 = vec![-1.0, 0.0, 0.0];
+        assert!((geodesic_distance(&p1, &p2) - PI / 2.0).abs() < 1e-12);
+        assert!((geodesic_distance(&p1, &p3) - PI).abs() < 1e-12);
+        assert_eq!(geodesic_distance(&p1, &p1), 0.0);
+    }
+
+    #[test]
+    fn test_random_sphere() {
+        let points = PointSet::init_from_random_sphere(500, 4, 51);
+        for point in points.points.iter() {
+            assert!((norm(point) - 1.0).abs() < 1e-12);
+        }
+        assert!(PointSet::init_from_preset_sphere(vec![vec![0.0, 0.0]]).is_err());
+    }
+}
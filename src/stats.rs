@@ -0,0 +1,191 @@
+//! Statistics about the executions of the WSP algorithm, for experiment tracking.
+
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::ops::AddAssign;
+
+/// Statistics collected during the execution(s) of the WSP algorithm.
+/// The times are given in seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RunStats {
+    /// Number of points removed from the set
+    pub nb_removed: usize,
+    /// Number of points used as origins
+    pub nb_origins: usize,
+    /// Number of candidate points examined by the main loop of the algorithm
+    pub nb_iterations: usize,
+    /// Number of executions of the algorithm (e.g., the iterations of the adaptive algorithm)
+    pub nb_runs: usize,
+    /// Time spent resolving the distance and building the neighbour index
+    pub setup_time: f64,
+    /// Time spent in the main loop of the algorithm
+    pub loop_time: f64,
+}
+
+impl RunStats {
+    /// Stores the statistics in a CSV file, with a header row naming each field.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 20, 51);
+    /// let stats = wsp::wsp(&mut points, 3.0);
+    /// stats.save_in_csv("stats.csv").unwrap();
+    /// # std::fs::remove_file("stats.csv").unwrap();
+    /// ```
+    pub fn save_in_csv(&self, filepath: &str) -> Result<(), Box<dyn Error>> {
+        let mut wrt = csv::Writer::from_path(filepath)?;
+        wrt.serialize(self)?;
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+impl AddAssign for RunStats {
+    fn add_assign(&mut self, other: RunStats) {
+        self.nb_removed += other.nb_removed;
+        self.nb_origins += other.nb_origins;
+        self.nb_iterations += other.nb_iterations;
+        self.nb_runs += other.nb_runs;
+        self.setup_time += other.setup_time;
+        self.loop_time += other.loop_time;
+    }
+}
+
+/// Timings and counters of the phases of the work done on a PointSet since its creation, to find which
+/// phase dominates a slow run. Returned by [`crate::PointSet::stats`]. The times are given in seconds.
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(2000, 10, 51);
+/// wsp::adaptive_wsp(&mut points, 100, false);
+/// let stats = points.stats();
+/// assert_eq!(stats.nb_distances, 2000 * 1999 / 2);
+/// println!("{}", stats);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PhaseStats {
+    /// Time spent computing the distance matrix (dense backend)
+    pub matrix_time: f64,
+    /// Number of distances computed for the distance matrix
+    pub nb_distances: usize,
+    /// Time spent sorting the neighbours of the points (dense backend)
+    pub sort_time: f64,
+    /// Number of sorted lists of neighbours, including the extensions of truncated lists (see
+    /// [`crate::PointSet::with_nb_neighbours`])
+    pub nb_sorted_lists: usize,
+    /// Time spent building the spatial index (k-d tree or grid)
+    pub index_time: f64,
+    /// Number of builds of the spatial index. The grid is rebuilt when the minimal distance changes much
+    pub nb_index_builds: usize,
+    /// Time spent in the selection loop of the algorithm, over all the runs
+    pub loop_time: f64,
+    /// Number of executions of the selection loop
+    pub nb_runs: usize,
+    /// Number of candidate points examined by the selection loop, over all the runs
+    pub nb_iterations: usize,
+    /// Number of points removed by the selection loop, over all the runs
+    pub nb_removed: usize,
+}
+
+impl PhaseStats {
+    /// Accounts for the selection loop of a run.
+    pub(crate) fn add_run(&mut self, run: &RunStats) {
+        self.loop_time += run.loop_time;
+        self.nb_runs += run.nb_runs;
+        self.nb_iterations += run.nb_iterations;
+        self.nb_removed += run.nb_removed;
+    }
+}
+
+impl AddAssign for PhaseStats {
+    fn add_assign(&mut self, other: PhaseStats) {
+        self.matrix_time += other.matrix_time;
+        self.nb_distances += other.nb_distances;
+        self.sort_time += other.sort_time;
+        self.nb_sorted_lists += other.nb_sorted_lists;
+        self.index_time += other.index_time;
+        self.nb_index_builds += other.nb_index_builds;
+        self.loop_time += other.loop_time;
+        self.nb_runs += other.nb_runs;
+        self.nb_iterations += other.nb_iterations;
+        self.nb_removed += other.nb_removed;
+    }
+}
+
+impl fmt::Display for PhaseStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "distance matrix: {:.6}s, {} distances",
+            self.matrix_time, self.nb_distances
+        )?;
+        writeln!(
+            f,
+            "neighbour sorting: {:.6}s, {} lists",
+            self.sort_time, self.nb_sorted_lists
+        )?;
+        writeln!(
+            f,
+            "spatial index: {:.6}s, {} builds",
+            self.index_time, self.nb_index_builds
+        )?;
+        write!(
+            f,
+            "selection loop: {:.6}s, {} runs, {} iterations, {} removals",
+            self.loop_time, self.nb_runs, self.nb_iterations, self.nb_removed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{adaptive_wsp, wsp, wsp_sweep, Backend, Metric, PointSet};
+
+    #[test]
+    fn test_counters() {
+        let mut points = PointSet::init_from_random(500, 3, 51);
+        let stats = wsp(&mut points, 0.2);
+        assert_eq!(stats.nb_runs, 1);
+        assert_eq!(stats.nb_removed, 500 - points.nb_active);
+        assert!(stats.nb_origins <= points.nb_active);
+        assert!(stats.nb_iterations >= stats.nb_removed + stats.nb_origins - 1);
+
+        let mut points = PointSet::init_from_random(500, 3, 51);
+        let stats = wsp_sweep(&mut points, 0.2);
+        assert_eq!(stats.nb_origins, points.nb_active);
+
+        let mut points = PointSet::init_from_random(500, 3, 51);
+        let stats = adaptive_wsp(&mut points, 50, false);
+        assert!(stats.nb_runs > 1);
+        assert!(stats.nb_removed > 500 - points.nb_active);
+    }
+
+    #[test]
+    fn test_phases() {
+        let mut points = PointSet::init_from_random(500, 3, 51).with_nb_neighbours(10);
+        let run = adaptive_wsp(&mut points, 50, false);
+        let phases = points.stats();
+        assert_eq!(phases.nb_distances, 500 * 499 / 2);
+        assert!(phases.nb_sorted_lists > 500);
+        assert_eq!(phases.nb_index_builds, 0);
+        assert_eq!(phases.nb_runs, run.nb_runs);
+        assert_eq!(phases.nb_iterations, run.nb_iterations);
+        assert_eq!(phases.nb_removed, run.nb_removed);
+
+        for backend in [Backend::KdTree, Backend::Grid] {
+            let candidates = PointSet::init_from_random(500, 3, 51).points;
+            let mut points =
+                PointSet::init_from_preset_with_backend(candidates, Metric::Manhattan, backend);
+            wsp(&mut points, 0.2);
+            wsp(&mut points, 0.2);
+            let phases = points.stats();
+            assert_eq!(phases.nb_distances, 0);
+            assert_eq!(phases.nb_sorted_lists, 0);
+            assert_eq!(phases.nb_index_builds, 1);
+            assert_eq!(phases.nb_runs, 2);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! Streaming mode: the candidates are generated or read one at a time and immediately tested against the
+//! design, without storing the pool. The memory only depends on the size of the design, so the stream
+//! of candidates may be unbounded.
+//!
+//! A candidate is kept if it is at least `d_min` away from all the points already kept. Contrary to
+//! [`crate::wsp`], the design depends on the order of the stream instead of the chain of closest origins.
+
+use crate::{Metric, Summation, WspError};
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Design built from a stream of candidates. The kept points are indexed in a hash grid whose cells
+/// are `d_min` wide, so that testing a candidate only looks at the points of the neighbouring cells.
+///
+/// # Example
+///
+/// ```
+/// let mut design = wsp::StreamingWsp::new(0.1);
+/// assert_eq!(design.offer(vec![0.5, 0.5]), Ok(true));
+/// assert_eq!(design.offer(vec![0.55, 0.5]), Ok(false));
+/// assert_eq!(design.offer(vec![0.8, 0.5]), Ok(true));
+/// assert_eq!(design.points().len(), 2);
+/// assert_eq!(design.nb_offered(), 3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamingWsp {
+    d_min: f64,
+    metric: Metric,
+    summation: Summation,
+    points: Vec<Vec<f64>>,
+    cells: HashMap<Vec<i64>, Vec<usize>>,
+    nb_offered: usize,
+}
+
+impl StreamingWsp {
+    /// Creates an empty design whose points are at least `d_min` away from each other,
+    /// with the Manhattan distance.
+    pub fn new(d_min: f64) -> StreamingWsp {
+        StreamingWsp::with_metric(d_min, Metric::Manhattan)
+    }
+
+    /// Creates an empty design whose points are at least `d_min` away from each other,
+    /// with the given distance.
+    pub fn with_metric(d_min: f64, metric: Metric) -> StreamingWsp {
+        assert!(
+            d_min > 0.0,
+            "The minimal distance must be positive, got {}",
+            d_min
+        );
+        StreamingWsp {
+            d_min,
+            metric,
+            summation: Summation::default(),
+            points: Vec::new(),
+            cells: HashMap::new(),
+            nb_offered: 0,
+        }
+    }
+
+    /// Returns the same design, summing the per-dimension terms of the distances with the given algorithm.
+    pub fn with_summation(mut self, summation: Summation) -> StreamingWsp {
+        self.summation = summation;
+        self
+    }
+
+    /// Tests a candidate against the design, and keeps it if it is at least `d_min` away from all the
+    /// points of the design. Returns whether the candidate is kept. The candidates must not contain
+    /// missing values, and must all have the dimension of the first candidate.
+    pub fn offer(&mut self, candidate: Vec<f64>) -> Result<bool, WspError> {
+        if let Some(first) = self.points.first() {
+            if first.len() != candidate.len() {
+                return Err(WspError::DimensionMismatch {
+                    expected: first.len(),
+                    found: candidate.len(),
+                });
+            }
+        }
+        if let Some(dim) = candidate.iter().position(|x| x.is_nan()) {
+            return Err(WspError::MissingValue {
+                point: self.nb_offered,
+                dim,
+            });
+        }
+        self.nb_offered += 1;
+
+        let cell = self.cell_of(&candidate);
+        if self.is_covered(&candidate, &cell) {
+            return Ok(false);
+        }
+        self.cells.entry(cell).or_default().push(self.points.len());
+        self.points.push(candidate);
+        Ok(true)
+    }
+
+    /// Whether a point of the design is closer than `d_min` to the candidate. For all the metrics, two points
+    /// closer than `d_min` differ by less than `d_min` on each coordinate, so they lie in neighbouring cells.
+    fn is_covered(&self, candidate: &[f64], cell: &[i64]) -> bool {
+        let covers = |idxs: &Vec<usize>| {
+            idxs.iter().any(|&i| {
+                self.metric
+                    .distance_with(candidate, &self.points[i], self.summation)
+                    < self.d_min
+            })
+        };
+        let nb_neighbours = 3usize.checked_pow(cell.len() as u32);
+        if nb_neighbours.is_some_and(|nb| nb <= self.cells.len()) {
+            // Enumerate the neighbouring cells, as an odometer over the offsets -1, 0 and 1
+            let mut offsets = vec![-1i64; cell.len()];
+            loop {
+                let neighbour: Vec<i64> = cell.iter().zip(&offsets).map(|(c, o)| c + o).collect();
+                if self.cells.get(&neighbour).is_some_and(covers) {
+                    return true;
+                }
+                match offsets.iter().position(|&o| o < 1) {
+                    Some(dim) => {
+                        offsets[..dim].iter_mut().for_each(|o| *o = -1);
+                        offsets[dim] += 1;
+                    }
+                    None => return false,
+                }
+            }
+        }
+        // Fewer occupied cells than neighbouring cells (e.g. in high dimension): scan the occupied cells
+        self.cells.iter().any(|(other, idxs)| {
+            other.iter().zip(cell).all(|(o, c)| (o - c).abs() <= 1) && covers(idxs)
+        })
+    }
+
+    fn cell_of(&self, point: &[f64]) -> Vec<i64> {
+        point
+            .iter()
+            .map(|x| (x / self.d_min).floor() as i64)
+            .collect()
+    }
+
+    /// Returns the points of the design, in the order of the stream.
+    pub fn points(&self) -> &[Vec<f64>] {
+        &self.points
+    }
+
+    /// Consumes the design and returns its points, in the order of the stream.
+    pub fn into_points(self) -> Vec<Vec<f64>> {
+        self.points
+    }
+
+    /// Returns the number of candidates offered to the design so far.
+    pub fn nb_offered(&self) -> usize {
+        self.nb_offered
+    }
+}
+
+/// Builds a design from a stream of candidates, see [`StreamingWsp`]. Returns the points of the design,
+/// in the order of the stream.
+///
+/// # Arguments
+///
+/// * `candidates` - The stream of candidate points.
+/// * `d_min` - The minimal distance between the points of the design, with the Manhattan distance.
+///
+/// # Example
+///
+/// ```
+/// use rand::SeedableRng;
+///
+/// // A million candidates, generated on the fly
+/// let mut rng = rand::rngs::SmallRng::seed_from_u64(51);
+/// let candidates = wsp::uniform_candidates(3, &mut rng).take(1_000_000);
+/// let design = wsp::stream_wsp(candidates, 0.3).unwrap();
+/// assert!(design.len() > 50);
+/// ```
+pub fn stream_wsp<I>(candidates: I, d_min: f64) -> Result<Vec<Vec<f64>>, WspError>
+where
+    I: IntoIterator<Item = Vec<f64>>,
+{
+    let mut design = StreamingWsp::new(d_min);
+    for candidate in candidates {
+        design.offer(candidate)?;
+    }
+    Ok(design.into_points())
+}
+
+/// Returns an unbounded stream of points of dimension `nb_dim` drawn uniformly in the unit cube. The stream
+/// draws the same points as [`crate::PointSet::init_from_random_with_rng`] with the same generator.
+pub fn uniform_candidates<R: Rng + ?Sized>(
+    nb_dim: usize,
+    rng: &mut R,
+) -> impl Iterator<Item = Vec<f64>> + '_ {
+    std::iter::repeat_with(move || (0..nb_dim).map(|_| rng.gen::<f64>()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{min_distance, PointSet};
+    use rand::rngs::SmallRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_stream() {
+        for nb_dim in [2, 12] {
+            let d_min = if nb_dim == 2 { 0.1 } else { 2.0 };
+            let candidates: Vec<Vec<f64>> =
+                uniform_candidates(nb_dim, &mut SmallRng::seed_from_u64(51))
+                    .take(3000)
+                    .collect();
+            assert_eq!(
+                candidates,
+                PointSet::init_from_random(3000, nb_dim, 51).points
+            );
+            let design = stream_wsp(candidates.iter().cloned(), d_min).unwrap();
+
+            // Same design as a brute-force sequential inhibition
+            let mut expected: Vec<Vec<f64>> = Vec::new();
+            for candidate in candidates {
+                if expected
+                    .iter()
+                    .all(|p| Metric::Manhattan.distance(p, &candidate) >= d_min)
+                {
+                    expected.push(candidate);
+                }
+            }
+            assert_eq!(design, expected);
+            assert!(min_distance(&PointSet::init_from_preset(design)) >= d_min);
+        }
+
+        let mut design = StreamingWsp::new(0.1);
+        design.offer(vec![0.0, 0.0]).unwrap();
+        assert_eq!(
+            design.offer(vec![0.0]),
+            Err(WspError::DimensionMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert!(design.offer(vec![f64::NAN, 1.0]).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+//! Sub-sampling of huge candidate pools. The cost of WSP grows quadratically with the number of
+//! candidates, so enormous pools are first reduced with a fast sub-sampling, and WSP then runs
+//! exactly on the survivors.
+
+use crate::{derive_seed, wsp, DMin, PointSet, RunStats, SeedStream};
+use rand::rngs::SmallRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Method used to reduce a pool of candidate points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subsampling {
+    /// Keeps the given number of candidates, drawn uniformly at random without replacement.
+    Uniform(usize),
+    /// Splits the space in a grid of cubic cells of the given side, and keeps the first candidate of
+    /// each non-empty cell. Dense regions of the pool are thinned while sparse regions are kept.
+    Grid(f64),
+}
+
+impl fmt::Display for Subsampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Subsampling::Uniform(nb_points) => write!(f, "uniform:{}", nb_points),
+            Subsampling::Grid(cell_size) => write!(f, "grid:{}", cell_size),
+        }
+    }
+}
+
+impl FromStr for Subsampling {
+    type Err = String;
+
+    /// Parses "uniform:<nb-points>" or "grid:<cell-size>".
+    fn from_str(s: &str) -> Result<Subsampling, String> {
+        let (method, value) = s.split_once(':').ok_or_else(|| {
+            format!(
+                "Invalid sub-sampling \"{}\", expected uniform:<nb-points> or grid:<cell-size>",
+                s
+            )
+        })?;
+        match method {
+            "uniform" => value
+                .parse()
+                .map(Subsampling::Uniform)
+                .map_err(|_| format!("Invalid number of points \"{}\"", value)),
+            "grid" => match value.parse::<f64>() {
+                Ok(cell_size) if cell_size > 0.0 => Ok(Subsampling::Grid(cell_size)),
+                _ => Err(format!("Invalid cell size \"{}\"", value)),
+            },
+            _ => Err(format!("Unknown sub-sampling: {}", method)),
+        }
+    }
+}
+
+/// Returns the indexes of the candidates kept by the sub-sampling, in increasing order.
+///
+/// # Arguments
+///
+/// * `points` - The pool of candidate points.
+/// * `subsampling` - The sub-sampling method.
+/// * `seed` - The seed of the uniform sub-sampling.
+///
+/// # Example
+///
+/// ```
+/// let points = vec![vec![0.0, 0.0], vec![0.01, 0.0], vec![0.5, 0.5], vec![0.9, 0.1]];
+/// assert_eq!(wsp::subsample(&points, wsp::Subsampling::Grid(0.25), 51), vec![0, 2, 3]);
+/// assert_eq!(wsp::subsample(&points, wsp::Subsampling::Uniform(2), 51).len(), 2);
+/// ```
+pub fn subsample(points: &[Vec<f64>], subsampling: Subsampling, seed: u64) -> Vec<usize> {
+    subsample_with_rng(points, subsampling, &mut SmallRng::seed_from_u64(seed))
+}
+
+/// Same as [`subsample`], drawing the uniform sub-sampling from a caller-supplied random number generator.
+pub fn subsample_with_rng<R: Rng + ?Sized>(
+    points: &[Vec<f64>],
+    subsampling: Subsampling,
+    rng: &mut R,
+) -> Vec<usize> {
+    match subsampling {
+        Subsampling::Uniform(nb_points) if nb_points >= points.len() => (0..points.len()).collect(),
+        Subsampling::Uniform(nb_points) => {
+            let mut idxs = sample(rng, points.len(), nb_points).into_vec();
+            idxs.sort_unstable();
+            idxs
+        }
+        Subsampling::Grid(cell_size) => {
+            assert!(
+                cell_size > 0.0,
+                "The cell size must be positive, got {}",
+                cell_size
+            );
+            let mut cells: HashSet<Vec<i64>> = HashSet::new();
+            (0..points.len())
+                .filter(|&i| {
+                    let cell = points[i]
+                        .iter()
+                        .map(|x| (x / cell_size).floor() as i64)
+                        .collect();
+                    cells.insert(cell)
+                })
+                .collect()
+        }
+    }
+}
+
+/// Two-stage pipeline for huge candidate pools: the pool is first reduced with [`subsample`], then WSP
+/// runs exactly on the survivors. Returns the PointSet of the survivors, with the result of WSP, and the
+/// statistics of the run. The relative minimal distances (see [`DMin`]) refer to the survivors.
+/// The seeds of the sub-sampling and of the origin choice are derived from `seed` (see [`derive_seed`]).
+///
+/// # Arguments
+///
+/// * `points` - The pool of candidate points.
+/// * `subsampling` - The sub-sampling method.
+/// * `seed` - The seed of the run.
+/// * `d_min` - The desired minimal distance between all remaining points. See [`crate::wsp`].
+///
+/// # Example
+///
+/// ```
+/// let pool = wsp::PointSet::init_from_random(5000, 2, 51).points;
+/// let (points, _) = wsp::subsampled_wsp(pool, wsp::Subsampling::Uniform(1000), 51, 0.05);
+/// assert_eq!(points.points.len(), 1000);
+/// ```
+pub fn subsampled_wsp(
+    points: Vec<Vec<f64>>,
+    subsampling: Subsampling,
+    seed: u64,
+    d_min: impl Into<DMin>,
+) -> (PointSet, RunStats) {
+    let mut kept = subsample(
+        &points,
+        subsampling,
+        derive_seed(seed, SeedStream::Subsampling),
+    )
+    .into_iter()
+    .peekable();
+    let survivors: Vec<Vec<f64>> = points
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| kept.next_if_eq(&i).is_some())
+        .map(|(_, point)| point)
+        .collect();
+    let mut set = PointSet::init_from_preset(survivors).with_seed(seed);
+    let stats = wsp(&mut set, d_min);
+    (set, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_subsampling() {
+        let points = PointSet::init_from_random(2000, 2, 51).points;
+        let kept = subsample(&points, Subsampling::Grid(0.1), 51);
+        // At most one point per cell of the 10x10 grid, and all cells are hit
+        assert_eq!(kept.len(), 100);
+        assert!(kept.windows(2).all(|w| w[0] < w[1]));
+
+        assert_eq!(
+            "grid:0.1".parse::<Subsampling>(),
+            Ok(Subsampling::Grid(0.1))
+        );
+        assert!("grid:-1".parse::<Subsampling>().is_err());
+        assert_eq!(
+            "uniform:10".parse::<Subsampling>(),
+            Ok(Subsampling::Uniform(10))
+        );
+    }
+}
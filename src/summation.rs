@@ -0,0 +1,102 @@
+//! Summation algorithms of the distance kernels. In high dimensions, with coordinates of very
+//! different magnitudes, the rounding errors of a naive summation can decide on which side of
+//! the minimal distance a pair of points falls. A compensated summation makes this decision stable.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Algorithm used to sum the per-dimension terms of the distances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Summation {
+    /// Sequential summation. Fastest, with an error growing with the dimension.
+    #[default]
+    Naive,
+    /// Neumaier's variant of the Kahan compensated summation. The error does not depend
+    /// on the dimension, at the cost of about four times more floating point operations.
+    Neumaier,
+}
+
+impl Summation {
+    /// Sums the values with the algorithm.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let values = [1.0, 1e100, 1.0, -1e100];
+    /// assert_eq!(wsp::Summation::Naive.sum(values), 0.0);
+    /// assert_eq!(wsp::Summation::Neumaier.sum(values), 2.0);
+    /// ```
+    pub fn sum<I: IntoIterator<Item = f64>>(self, values: I) -> f64 {
+        match self {
+            Summation::Naive => values.into_iter().fold(0.0, |sum, x| sum + x),
+            Summation::Neumaier => {
+                let (sum, compensation) =
+                    values
+                        .into_iter()
+                        .fold((0.0f64, 0.0f64), |(sum, compensation), x| {
+                            let t = sum + x;
+                            let lost = if sum.abs() >= x.abs() {
+                                (sum - t) + x
+                            } else {
+                                (x - t) + sum
+                            };
+                            (t, compensation + lost)
+                        });
+                sum + compensation
+            }
+        }
+    }
+}
+
+impl fmt::Display for Summation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Summation::Naive => "naive",
+            Summation::Neumaier => "neumaier",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Summation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Summation, String> {
+        match s {
+            "naive" => Ok(Summation::Naive),
+            "neumaier" => Ok(Summation::Neumaier),
+            _ => Err(format!("Unknown summation: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{wsp, Backend, Metric, PointSet};
+
+    #[test]
+    fn test_compensated_threshold() {
+        // Each small coordinate is lost when added to 1.0 by the naive summation
+        let mut p1 = vec![1.0];
+        p1.extend(vec![1e-16; 100]);
+        let p2 = vec![0.0; 101];
+        let d_min = 1.0 + 5e-15;
+        assert!(Metric::Manhattan.distance(&p1, &p2) < d_min);
+        assert!(Metric::Manhattan.distance_with(&p1, &p2, Summation::Neumaier) > d_min);
+
+        for backend in [Backend::Dense, Backend::KdTree] {
+            let points = vec![p1.clone(), p2.clone()];
+            let mut naive =
+                PointSet::init_from_preset_with_backend(points.clone(), Metric::Manhattan, backend);
+            wsp(&mut naive, d_min);
+            assert_eq!(naive.nb_active, 1);
+
+            let mut compensated =
+                PointSet::init_from_preset_with_backend(points, Metric::Manhattan, backend)
+                    .with_summation(Summation::Neumaier);
+            wsp(&mut compensated, d_min);
+            assert_eq!(compensated.nb_active, 2);
+        }
+    }
+}
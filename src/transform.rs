@@ -0,0 +1,229 @@
+//! Per-dimension normalization of the candidate points onto the unit cube, so that all dimensions
+//! weigh the same in the distances whatever their physical units. The resulting design can be
+//! exported either in normalized coordinates or in the original units.
+
+use crate::{PointSet, WspError};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Affine transform mapping each dimension from `[lower, upper]` onto `[0, 1]`:
+/// `normalized = (original - lower) / (upper - lower)`.
+/// Constant dimensions (`lower == upper`) are mapped onto 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Normalization {
+    /// Lower bound of each dimension, in original units
+    pub lower: Vec<f64>,
+    /// Upper bound of each dimension, in original units
+    pub upper: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct NormalizationRecord {
+    dim: usize,
+    lower: f64,
+    upper: f64,
+}
+
+impl Normalization {
+    /// Computes the bounding box of the points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points = vec![vec![0.0, 10.0], vec![2.0, 30.0]];
+    /// let normalization = wsp::Normalization::fit(&points);
+    /// assert_eq!(normalization.normalize(&[1.0, 30.0]), vec![0.5, 1.0]);
+    /// assert_eq!(normalization.denormalize(&[0.5, 1.0]), vec![1.0, 30.0]);
+    /// ```
+    pub fn fit(points: &[Vec<f64>]) -> Normalization {
+        let nb_dim = points.first().map_or(0, |p| p.len());
+        let mut normalization = Normalization {
+            lower: vec![f64::MAX; nb_dim],
+            upper: vec![f64::MIN; nb_dim],
+        };
+        for point in points.iter() {
+            for (d, &x) in point.iter().enumerate() {
+                normalization.lower[d] = normalization.lower[d].min(x);
+                normalization.upper[d] = normalization.upper[d].max(x);
+            }
+        }
+        normalization
+    }
+
+    /// Maps a point in original units onto the unit cube.
+    pub fn normalize(&self, point: &[f64]) -> Vec<f64> {
+        point
+            .iter()
+            .enumerate()
+            .map(|(d, &x)| match self.upper[d] - self.lower[d] {
+                range if range > 0.0 => (x - self.lower[d]) / range,
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    /// Maps a point of the unit cube back to original units.
+    pub fn denormalize(&self, point: &[f64]) -> Vec<f64> {
+        point
+            .iter()
+            .enumerate()
+            .map(|(d, &x)| self.lower[d] + x * (self.upper[d] - self.lower[d]))
+            .collect()
+    }
+
+    /// Stores the transform in a CSV file, with one row `dim,lower,upper` per dimension.
+    pub fn save_in_csv(&self, filepath: &str) -> Result<(), Box<dyn Error>> {
+        let mut wrt = csv::Writer::from_path(filepath)?;
+        for (dim, (&lower, &upper)) in self.lower.iter().zip(self.upper.iter()).enumerate() {
+            wrt.serialize(NormalizationRecord { dim, lower, upper })?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+/// Representation of the coordinates of exported points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputScale {
+    /// Coordinates as used by the algorithm, i.e. on the unit cube when the points are normalized
+    Normalized,
+    /// Coordinates in the original units of the candidate points
+    Original,
+}
+
+impl fmt::Display for OutputScale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            OutputScale::Normalized => "normalized",
+            OutputScale::Original => "original",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for OutputScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputScale, String> {
+        match s {
+            "normalized" => Ok(OutputScale::Normalized),
+            "original" => Ok(OutputScale::Original),
+            _ => Err(format!("Unknown output scale: {}", s)),
+        }
+    }
+}
+
+impl PointSet {
+    /// Creates a 'PointSet' from points in arbitrary units, normalized onto the unit cube
+    /// (see [`Normalization`]). The distances, and thus the minimal distance of the algorithm,
+    /// are expressed in normalized coordinates.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The pre-initialised set of points, in original units.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let points = vec![vec![0.0, 100.0], vec![1.0, 300.0], vec![0.5, 200.0]];
+    /// let mut pointset = wsp::PointSet::init_from_preset_normalized(points.clone()).unwrap();
+    /// assert_eq!(pointset.points[2], vec![0.5, 0.5]);
+    ///
+    /// wsp::wsp(&mut pointset, 0.1);
+    /// assert_eq!(pointset.get_remaining_in(wsp::OutputScale::Original), points);
+    /// ```
+    pub fn init_from_preset_normalized(points: Vec<Vec<f64>>) -> Result<PointSet, WspError> {
+        let nb_dim = points.first().map_or(0, |p| p.len());
+        if let Some(point) = points.iter().find(|p| p.len() != nb_dim) {
+            return Err(WspError::DimensionMismatch {
+                expected: nb_dim,
+                found: point.len(),
+            });
+        }
+        if let Some(err) = crate::missing::first_missing(&points) {
+            return Err(err);
+        }
+        let normalization = Normalization::fit(&points);
+        let normalized = points.iter().map(|p| normalization.normalize(p)).collect();
+        let mut set = PointSet::init_from_preset(normalized);
+        set.normalization = Some(normalization);
+        Ok(set)
+    }
+
+    /// Returns the normalization applied to the points, if any.
+    pub fn normalization(&self) -> Option<&Normalization> {
+        self.normalization.as_ref()
+    }
+
+    /// Returns a new vector containing only the active points of the PointSet, in the given representation.
+    /// Without normalization, both representations are the same.
+    pub fn get_remaining_in(&self, scale: OutputScale) -> Vec<Vec<f64>> {
+        match (scale, self.normalization.as_ref()) {
+            (OutputScale::Original, Some(normalization)) => self
+                .iter_remaining()
+                .map(|p| normalization.denormalize(p))
+                .collect(),
+            _ => self.get_remaining(),
+        }
+    }
+
+    /// Returns a new vector containing only the active points of the PointSet, mapped from the
+    /// unit cube onto the given ranges: coordinate `x` of dimension `d` becomes
+    /// `bounds[d].0 + x * (bounds[d].1 - bounds[d].0)`.
+    /// The coordinates are taken as used by the algorithm, i.e. normalized if the points are normalized.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounds` - The (lower, upper) range of each dimension.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 2, 51);
+    /// wsp::wsp(&mut points, 0.1);
+    /// let design = points.remaining_scaled(&[(0.0, 10.0), (-5.0, 5.0)]);
+    /// assert!(design.iter().all(|p| p[1] >= -5.0 && p[1] <= 5.0));
+    /// ```
+    pub fn remaining_scaled(&self, bounds: &[(f64, f64)]) -> Vec<Vec<f64>> {
+        let nb_dim = self.points.first().map_or(0, |p| p.len());
+        assert_eq!(
+            bounds.len(),
+            nb_dim,
+            "Expected bounds for {} dimensions, got {}",
+            nb_dim,
+            bounds.len()
+        );
+        let scaling = Normalization {
+            lower: bounds.iter().map(|b| b.0).collect(),
+            upper: bounds.iter().map(|b| b.1).collect(),
+        };
+        self.iter_remaining()
+            .map(|p| scaling.denormalize(p))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_dimension() {
+        let points = vec![vec![1.0, 5.0], vec![3.0, 5.0]];
+        let set = PointSet::init_from_preset_normalized(points.clone()).unwrap();
+        assert_eq!(set.points, vec![vec![0.0, 0.0], vec![1.0, 0.0]]);
+        assert_eq!(set.get_remaining_in(OutputScale::Original), points);
+        assert_eq!(set.get_remaining_in(OutputScale::Normalized), set.points);
+    }
+
+    #[test]
+    fn test_remaining_scaled() {
+        let set = PointSet::init_from_preset(vec![vec![0.0, 0.5], vec![1.0, 0.25]]);
+        assert_eq!(
+            set.remaining_scaled(&[(10.0, 20.0), (-4.0, 4.0)]),
+            vec![vec![10.0, 0.0], vec![20.0, -2.0]]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Attribution of each removed candidate to its closest remaining point (a discrete Voronoi cell).
+//! The mapping lets users aggregate observations of the full candidate set onto the design, and the
+//! size of the cells gives an importance weight to each design point, e.g. for weighted surrogate fitting.
+
+use crate::kdtree::KdTree;
+use crate::{BitSet, PointSet};
+use serde::Serialize;
+use std::error::Error;
+
+/// Counts, for each active point of the PointSet, the number of inactive candidates closer to it
+/// than to any other active point. Ties are broken arbitrarily. The active point itself is not counted:
+/// `1 + count` is the number of candidates of its cell.
+///
+/// Returns the counts in the order of the active points, i.e. the order of [`PointSet::get_remaining`].
+///
+/// # Example
+///
+/// ```
+/// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+/// wsp::wsp(&mut points, 2.0);
+/// let weights = wsp::voronoi_weights(&points);
+/// assert_eq!(weights.len(), points.nb_active);
+/// assert_eq!(weights.iter().sum::<usize>(), 1000 - points.nb_active);
+/// ```
+pub fn voronoi_weights(set: &PointSet) -> Vec<usize> {
+    let rows = design_rows(set);
+    let mut weights = vec![0; set.nb_active];
+    for (_, representative) in set.representative_map() {
+        weights[rows[representative]] += 1;
+    }
+    weights
+}
+
+/// Returns the position of each active point among the active points.
+fn design_rows(set: &PointSet) -> Vec<usize> {
+    let mut rows = vec![0; set.points.len()];
+    for (row, i) in (0..set.points.len()).filter(|&i| set.active[i]).enumerate() {
+        rows[i] = row;
+    }
+    rows
+}
+
+#[derive(Serialize)]
+struct RepresentativeRecord {
+    candidate: usize,
+    representative: usize,
+    design_row: usize,
+}
+
+impl PointSet {
+    /// Returns, for every removed (inactive) candidate, the pair (candidate, representative), where the
+    /// representative is the closest active point. Ties are broken arbitrarily. Indexes refer to the
+    /// points of the PointSet. The result is empty if no point is active.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let mut points = wsp::PointSet::init_from_random(1000, 10, 51);
+    /// wsp::wsp(&mut points, 2.0);
+    /// for (candidate, representative) in points.representative_map() {
+    ///     assert!(!points.active[candidate] && points.active[representative]);
+    /// }
+    /// ```
+    pub fn representative_map(&self) -> Vec<(usize, usize)> {
+        if self.nb_active == 0 {
+            return Vec::new();
+        }
+        let mut tree = KdTree::new(&self.points);
+        tree.sync(&self.active, &BitSet::new(self.points.len(), false));
+
+        (0..self.points.len())
+            .filter(|&i| !self.active[i])
+            .filter_map(|i| {
+                tree.nearest_available(
+                    self.metric,
+                    &self.points[i],
+                    &|j, limit| {
+                        self.metric.distance_below(
+                            &self.points[i],
+                            &self.points[j],
+                            limit,
+                            self.summation,
+                        )
+                    },
+                    &|j| self.active[j],
+                    0.0,
+                    0.0,
+                )
+                .map(|representative| (i, representative))
+            })
+            .collect()
+    }
+
+    /// Stores the mapping of [`PointSet::representative_map`] in a CSV file, with one row
+    /// `candidate,representative,design_row` per removed candidate. `design_row` is the row of the
+    /// representative among the remaining points, as written by [`PointSet::save_in_csv`].
+    pub fn save_representatives_in_csv(&self, filepath: &str) -> Result<(), Box<dyn Error>> {
+        let design_row = design_rows(self);
+        let mut wrt = csv::Writer::from_path(filepath)?;
+        for (candidate, representative) in self.representative_map() {
+            wrt.serialize(RepresentativeRecord {
+                candidate,
+                representative,
+                design_row: design_row[representative],
+            })?;
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voronoi_weights() {
+        let points: Vec<Vec<f64>> = [0.0, 0.1, 0.2, 0.45, 0.7, 1.0, 1.1]
+            .iter()
+            .map(|&x| vec![x])
+            .collect();
+        let mut pointset = PointSet::init_from_preset(points);
+        pointset.active = vec![true, false, false, false, false, true, false]
+            .into_iter()
+            .collect();
+        pointset.nb_active = 2;
+        assert_eq!(voronoi_weights(&pointset), vec![3, 2]);
+        assert_eq!(
+            pointset.representative_map(),
+            vec![(1, 0), (2, 0), (3, 0), (4, 5), (6, 5)]
+        );
+    }
+}